@@ -2,7 +2,7 @@ use confy::ConfyError;
 use derivative::Derivative;
 use serde::{Deserialize, Serialize};
 
-use crate::{ffmpeg::RenderSettings, osd::OsdOptions, srt::SrtOptions, util::AppUpdate, NAMESPACE};
+use crate::{ffmpeg::RenderSettings, font::FontLibrary, osd::OsdOptions, srt::SrtOptions, util::AppUpdate, NAMESPACE};
 
 #[derive(Debug, Deserialize, Serialize, Derivative)]
 #[derivative(Default)]
@@ -12,6 +12,8 @@ pub struct AppConfig {
     pub render_options: RenderSettings,
     pub app_update: AppUpdate,
     pub font_path: String,
+    pub srt_font_path: String,
+    pub font_library: FontLibrary,
 }
 
 const CONFIG_NAME: &str = "saved_settings";
@@ -10,5 +10,5 @@ pub use dependencies::{ffmpeg_available, ffprobe_available};
 pub use encoders::{Codec, Encoder};
 pub use message::{FromFfmpegMessage, ToFfmpegMessage};
 pub use render::{handle_decoder_events, start_video_render};
-pub use render_settings::RenderSettings;
+pub use render_settings::{RenderMode, RenderSettings, TransparentFormat};
 pub use video_info::VideoInfo;
@@ -1,4 +1,5 @@
 use std::{
+    fs,
     io::{self, Write},
     path::PathBuf,
     thread,
@@ -10,40 +11,52 @@ use ffmpeg_sidecar::{
     command::FfmpegCommand,
     event::{FfmpegEvent, LogLevel},
 };
+use image::Rgba;
 
-use super::{render_settings::RenderSettings, Encoder, FromFfmpegMessage, ToFfmpegMessage, VideoInfo};
-use crate::{
-    font,
-    osd::{self, OsdOptions},
-    overlay::FrameOverlayIter,
-    srt::{self, SrtOptions},
+use super::{
+    render_settings::{RenderMode, RenderSettings, TransparentFormat},
+    Encoder, FromFfmpegMessage, ToFfmpegMessage, VideoInfo,
 };
+use crate::overlay::{FrameOverlayIter, FramePixelFormat, OverlayLayer, OverlayStreamIter};
 
-#[tracing::instrument(skip(osd_frames, srt_frames, font_file), err)]
+const UPSCALED_SIZE: (u32, u32) = (2560, 1440);
+const UPSCALE_FILTER: &str = "scale=2560x1440:flags=bicubic";
+
+#[tracing::instrument(skip(layers), err)]
 pub fn start_video_render(
     ffmpeg_path: &PathBuf,
     input_video: &PathBuf,
     output_video: &PathBuf,
-    osd_frames: Vec<osd::Frame>,
-    srt_frames: Vec<srt::SrtFrame>,
-    font_file: font::FontFile,
-    srt_font: rusttype::Font<'static>,
-    osd_options: &OsdOptions,
-    srt_options: &SrtOptions,
+    layers: Vec<Box<dyn OverlayLayer>>,
     video_info: &VideoInfo,
     render_settings: &RenderSettings,
 ) -> Result<(Sender<ToFfmpegMessage>, Receiver<FromFfmpegMessage>), io::Error> {
-    let mut decoder_process = spawn_decoder(ffmpeg_path, input_video)?;
+    if render_settings.render_mode == RenderMode::FfmpegOverlay {
+        return start_overlay_render(
+            ffmpeg_path,
+            input_video,
+            output_video,
+            layers,
+            video_info,
+            render_settings,
+        );
+    }
+
+    // The decoder upscales the video so the overlay is drawn at the output resolution
+    let (width, height) = output_size(video_info, render_settings);
+    let pixel_format = render_settings.frame_pixel_format();
+    let mut decoder_process = spawn_decoder(ffmpeg_path, input_video, render_settings.upscale, pixel_format)?;
 
     let mut encoder_process = spawn_encoder(
         ffmpeg_path,
-        video_info.width,
-        video_info.height,
+        width,
+        height,
         video_info.frame_rate,
         render_settings.bitrate_mbps,
         &render_settings.encoder,
         output_video,
-        render_settings.upscale,
+        render_settings.transparent_format(),
+        pixel_format,
     )?;
 
     // Channels to communicate with ffmpeg handler thread
@@ -56,19 +69,11 @@ pub fn start_video_render(
             .iter()
             .expect("Failed to create `FfmpegIterator` for decoder"),
         decoder_process,
-        osd_frames,
-        srt_frames,
-        font_file,
-        srt_font,
-        osd_options,
-        srt_options,
+        layers,
         from_ffmpeg_tx.clone(),
         to_ffmpeg_rx,
-        if render_settings.use_chroma_key {
-            Some(render_settings.chroma_key)
-        } else {
-            None
-        },
+        frame_background(render_settings),
+        pixel_format,
     );
 
     // On another thread run the decoder iterator to completion and feed the output to the encoder's stdin
@@ -100,12 +105,94 @@ pub fn start_video_render(
     Ok((to_ffmpeg_tx, from_ffmpeg_rx))
 }
 
+/// Render only the overlay and let ffmpeg composite it onto the input video with the overlay filter. The video frames
+/// don't have to be decoded and copied by the tool which is a lot faster for high resolution videos.
+#[tracing::instrument(skip(layers), err)]
+fn start_overlay_render(
+    ffmpeg_path: &PathBuf,
+    input_video: &PathBuf,
+    output_video: &PathBuf,
+    layers: Vec<Box<dyn OverlayLayer>>,
+    video_info: &VideoInfo,
+    render_settings: &RenderSettings,
+) -> Result<(Sender<ToFfmpegMessage>, Receiver<FromFfmpegMessage>), io::Error> {
+    // The transparent export only contains the overlay so there's nothing to composite
+    let (width, height) = output_size(video_info, render_settings);
+    let mut encoder_process = if let Some(transparent_format) = render_settings.transparent_format() {
+        spawn_encoder(
+            ffmpeg_path,
+            width,
+            height,
+            video_info.frame_rate,
+            render_settings.bitrate_mbps,
+            &render_settings.encoder,
+            output_video,
+            Some(transparent_format),
+            FramePixelFormat::Rgba,
+        )?
+    } else {
+        spawn_overlay_encoder(ffmpeg_path, input_video, output_video, video_info, render_settings)?
+    };
+
+    // Channels to communicate with ffmpeg handler thread
+    let (from_ffmpeg_tx, from_ffmpeg_rx) = crossbeam_channel::unbounded();
+    let (to_ffmpeg_tx, to_ffmpeg_rx) = crossbeam_channel::unbounded();
+
+    // One extra frame so the overlay stream is never shorter than the video, the output ends with the shortest input
+    let frame_count = (video_info.duration.as_secs_f32() * video_info.frame_rate).ceil() as u32 + 1;
+    let overlay_stream_iter = OverlayStreamIter::new(width, height, video_info.frame_rate, frame_count, layers);
+
+    // On another thread render the overlay frames and feed them to the encoder's stdin. Stopping early closes stdin,
+    // after which ffmpeg finishes the output file.
+    let mut encoder_stdin = encoder_process.take_stdin().expect("Failed to get `stdin` for encoder");
+    thread::Builder::new()
+        .name("Overlay stream handler".into())
+        .spawn(move || {
+            tracing::info_span!("Overlay stream handler thread").in_scope(|| {
+                for frame in overlay_stream_iter {
+                    if let Ok(ToFfmpegMessage::AbortRender) = to_ffmpeg_rx.try_recv() {
+                        break;
+                    }
+                    if encoder_stdin.write_all(frame.as_raw()).is_err() {
+                        break;
+                    }
+                }
+            });
+        })
+        .expect("Failed to spawn overlay stream handler thread");
+
+    // On yet another thread run the encoder to completion
+    thread::Builder::new()
+        .name("Encoder handler".into())
+        .spawn(move || {
+            tracing::info_span!("Encoder handler thread").in_scope(|| {
+                encoder_process
+                    .iter()
+                    .expect("Failed to create encoder iterator")
+                    .for_each(|event| handle_overlay_encoder_events(event, &from_ffmpeg_tx));
+            });
+        })
+        .expect("Failed to spawn encoder handler thread");
+
+    Ok((to_ffmpeg_tx, from_ffmpeg_rx))
+}
+
 #[tracing::instrument(skip(ffmpeg_path))]
-pub fn spawn_decoder(ffmpeg_path: &PathBuf, input_video: &PathBuf) -> Result<FfmpegChild, io::Error> {
-    let decoder = FfmpegCommand::new_with_path(ffmpeg_path)
-        .create_no_window()
-        .input(input_video.to_str().unwrap())
-        .args(["-f", "rawvideo", "-pix_fmt", "rgba", "-"])
+pub fn spawn_decoder(
+    ffmpeg_path: &PathBuf,
+    input_video: &PathBuf,
+    upscale: bool,
+    pixel_format: FramePixelFormat,
+) -> Result<FfmpegChild, io::Error> {
+    let mut decoder_command = FfmpegCommand::new_with_path(ffmpeg_path);
+    decoder_command.create_no_window().input(input_video.to_str().unwrap());
+
+    if upscale {
+        decoder_command.args(["-vf", UPSCALE_FILTER]);
+    }
+
+    let decoder = decoder_command
+        .args(["-f", "rawvideo", "-pix_fmt", pixel_format.pix_fmt(), "-"])
         .spawn()?;
     Ok(decoder)
 }
@@ -119,27 +206,120 @@ pub fn spawn_encoder(
     bitrate_mbps: u32,
     video_encoder: &Encoder,
     output_video: &PathBuf,
-    upscale: bool,
+    transparent_format: Option<TransparentFormat>,
+    pixel_format: FramePixelFormat,
 ) -> Result<FfmpegChild, io::Error> {
     let mut encoder_command = FfmpegCommand::new_with_path(ffmpeg_path);
 
     encoder_command
         .create_no_window()
         .format("rawvideo")
-        .pix_fmt("rgba")
+        .pix_fmt(pixel_format.pix_fmt())
         .size(width, height)
         .rate(frame_rate)
         .input("-");
 
-    if upscale {
-        encoder_command.args(["-vf", "scale=2560x1440:flags=bicubic"]);
+    match transparent_format {
+        None => {
+            encoder_command
+                .pix_fmt("yuv420p")
+                .codec_video(&video_encoder.name)
+                .args(["-b:v", &format!("{}M", bitrate_mbps)])
+                .args(&video_encoder.extra_args);
+        }
+        Some(TransparentFormat::ProRes4444) => {
+            encoder_command.pix_fmt("yuva444p10le").codec_video("prores_ks").args([
+                "-profile:v",
+                "4444",
+                "-alpha_bits",
+                "16",
+            ]);
+        }
+        Some(TransparentFormat::Vp9Alpha) => {
+            encoder_command
+                .pix_fmt("yuva420p")
+                .codec_video("libvpx-vp9")
+                .args(["-b:v", &format!("{}M", bitrate_mbps)]);
+        }
+        Some(TransparentFormat::PngSequence) => {
+            // The output path is a pattern inside a directory for the frames
+            if let Some(directory) = output_video.parent() {
+                fs::create_dir_all(directory)?;
+            }
+            encoder_command.pix_fmt("rgba").codec_video("png");
+        }
+    }
+
+    encoder_command.overwrite().output(output_video.to_str().unwrap());
+
+    let encoder = encoder_command.spawn()?;
+    Ok(encoder)
+}
+
+/// Size of the rendered frames. When upscaling the overlay is drawn at the upscaled size so the glyphs don't get
+/// blurred by the scaling filter.
+fn output_size(video_info: &VideoInfo, render_settings: &RenderSettings) -> (u32, u32) {
+    if render_settings.upscale {
+        UPSCALED_SIZE
+    } else {
+        (video_info.width, video_info.height)
+    }
+}
+
+/// Color that replaces the video in the rendered frames, if any.
+fn frame_background(render_settings: &RenderSettings) -> Option<Rgba<u8>> {
+    if render_settings.use_transparent_export {
+        Some(Rgba([0, 0, 0, 0]))
+    } else if render_settings.use_chroma_key {
+        let [r, g, b] = render_settings.chroma_key.map(|c| (c * 255.0) as u8);
+        Some(Rgba([r, g, b, 255]))
+    } else {
+        None
+    }
+}
+
+#[tracing::instrument(skip(ffmpeg_path))]
+fn spawn_overlay_encoder(
+    ffmpeg_path: &PathBuf,
+    input_video: &PathBuf,
+    output_video: &PathBuf,
+    video_info: &VideoInfo,
+    render_settings: &RenderSettings,
+) -> Result<FfmpegChild, io::Error> {
+    let (width, height) = output_size(video_info, render_settings);
+    let mut encoder_command = FfmpegCommand::new_with_path(ffmpeg_path);
+    encoder_command.create_no_window();
+
+    if render_settings.use_chroma_key {
+        let [r, g, b] = render_settings.chroma_key.map(|c| (c * 255.0).round() as u8);
+        encoder_command.format("lavfi").input(format!(
+            "color=c=0x{r:02x}{g:02x}{b:02x}:s={width}x{height}:r={}",
+            video_info.frame_rate
+        ));
+    } else {
+        encoder_command.input(input_video.to_str().unwrap());
     }
 
     encoder_command
+        .format("rawvideo")
+        .pix_fmt("rgba")
+        .size(width, height)
+        .rate(video_info.frame_rate)
+        .input("-");
+
+    // The video is upscaled before the overlay is composited so the overlay stays sharp
+    let filter = if render_settings.upscale && !render_settings.use_chroma_key {
+        format!("[0:v]{UPSCALE_FILTER}[video];[video][1:v]overlay=shortest=1:format=auto")
+    } else {
+        "[0:v][1:v]overlay=shortest=1:format=auto".to_string()
+    };
+
+    encoder_command
+        .args(["-filter_complex", &format!("{filter}[out]"), "-map", "[out]"])
         .pix_fmt("yuv420p")
-        .codec_video(&video_encoder.name)
-        .args(["-b:v", &format!("{}M", bitrate_mbps)])
-        .args(&video_encoder.extra_args)
+        .codec_video(&render_settings.encoder.name)
+        .args(["-b:v", &format!("{}M", render_settings.bitrate_mbps)])
+        .args(&render_settings.encoder.extra_args)
         .overwrite()
         .output(output_video.to_str().unwrap());
 
@@ -167,6 +347,21 @@ fn handle_encoder_events(ffmpeg_event: FfmpegEvent, ffmpeg_sender: &Sender<FromF
     }
 }
 
+/// In overlay mode the encoder also decodes the video, so it reports the progress and when all frames are processed.
+fn handle_overlay_encoder_events(ffmpeg_event: FfmpegEvent, ffmpeg_sender: &Sender<FromFfmpegMessage>) {
+    match ffmpeg_event {
+        FfmpegEvent::Progress(p) => {
+            ffmpeg_sender.send(FromFfmpegMessage::Progress(p)).unwrap();
+        }
+        FfmpegEvent::LogEOF => {
+            tracing::info!("ffmpeg encoder EOF reached");
+            ffmpeg_sender.send(FromFfmpegMessage::DecoderFinished).unwrap();
+            ffmpeg_sender.send(FromFfmpegMessage::EncoderFinished).unwrap();
+        }
+        other_event => handle_encoder_events(other_event, ffmpeg_sender),
+    }
+}
+
 pub fn handle_decoder_events(ffmpeg_event: FfmpegEvent, ffmpeg_sender: &Sender<FromFfmpegMessage>) {
     match ffmpeg_event {
         FfmpegEvent::Progress(p) => {
@@ -185,3 +380,28 @@ pub fn handle_decoder_events(ffmpeg_event: FfmpegEvent, ffmpeg_sender: &Sender<F
         _ => {}
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn upscaled_render_size() {
+        let video_info = VideoInfo {
+            width: 1920,
+            height: 1080,
+            frame_rate: 60.0,
+            bitrate: 40_000_000,
+            duration: Duration::from_secs(10),
+            total_frames: 600,
+        };
+        let render_settings = RenderSettings {
+            upscale: true,
+            ..Default::default()
+        };
+        assert_eq!(output_size(&video_info, &render_settings), (2560, 1440));
+        assert_eq!(output_size(&video_info, &RenderSettings::default()), (1920, 1080));
+    }
+}
@@ -1,6 +1,12 @@
+use std::fmt::Display;
+
 use serde::{Deserialize, Serialize};
 
-use crate::ffmpeg::{Codec, Encoder};
+use crate::{
+    ffmpeg::{Codec, Encoder},
+    font::ScalingFilter,
+    overlay::{BlendMode, FramePixelFormat, ImageLayerOptions, LayerKind, TextLayerOptions},
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RenderSettings {
@@ -11,6 +17,97 @@ pub struct RenderSettings {
     pub upscale: bool,
     pub use_chroma_key: bool,
     pub chroma_key: [f32; 3],
+    pub render_osd: bool,
+    pub render_srt: bool,
+    pub render_images: bool,
+    pub render_texts: bool,
+    pub image_layers: Vec<ImageLayerOptions>,
+    pub text_layers: Vec<TextLayerOptions>,
+    /// Order the layers are drawn in, the last layer is drawn on top.
+    pub layer_order: Vec<LayerKind>,
+    pub glyph_scaling_filter: ScalingFilter,
+    pub blend_mode: BlendMode,
+    pub render_mode: RenderMode,
+    pub use_transparent_export: bool,
+    pub transparent_format: TransparentFormat,
+}
+
+impl RenderSettings {
+    /// Format of the transparent overlay export, or `None` when the overlay is rendered on the video.
+    pub fn transparent_format(&self) -> Option<TransparentFormat> {
+        self.use_transparent_export.then_some(self.transparent_format)
+    }
+
+    /// Pixel format of the decoded frames when the overlay is drawn on the decoded video.
+    pub fn frame_pixel_format(&self) -> FramePixelFormat {
+        if self.render_mode == RenderMode::Yuv && !self.use_transparent_export {
+            FramePixelFormat::Yuv420p
+        } else {
+            FramePixelFormat::Rgba
+        }
+    }
+}
+
+/// How the overlay is combined with the video.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RenderMode {
+    /// Every video frame is decoded, the overlay is drawn on it and the result is encoded.
+    #[default]
+    Composite,
+    /// Only the overlay is rendered as a transparent stream and ffmpeg's overlay filter composites it onto the video.
+    FfmpegOverlay,
+    /// Every video frame is decoded as YUV and the overlay is blended into it without converting the frame to RGBA.
+    /// The transparent export needs RGBA frames and always draws on decoded RGBA video.
+    Yuv,
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 3] = [RenderMode::Composite, RenderMode::FfmpegOverlay, RenderMode::Yuv];
+}
+
+/// Output formats that keep the alpha channel of the overlay so it can be composited in video editing software.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransparentFormat {
+    #[default]
+    ProRes4444,
+    Vp9Alpha,
+    PngSequence,
+}
+
+impl TransparentFormat {
+    pub const ALL: [TransparentFormat; 3] = [
+        TransparentFormat::ProRes4444,
+        TransparentFormat::Vp9Alpha,
+        TransparentFormat::PngSequence,
+    ];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TransparentFormat::ProRes4444 => "mov",
+            TransparentFormat::Vp9Alpha => "webm",
+            TransparentFormat::PngSequence => "png",
+        }
+    }
+}
+
+impl Display for TransparentFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransparentFormat::ProRes4444 => write!(f, "ProRes 4444 (.mov)"),
+            TransparentFormat::Vp9Alpha => write!(f, "VP9 with alpha (.webm)"),
+            TransparentFormat::PngSequence => write!(f, "PNG sequence"),
+        }
+    }
+}
+
+impl Display for RenderMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderMode::Composite => write!(f, "Draw on decoded video"),
+            RenderMode::FfmpegOverlay => write!(f, "ffmpeg overlay filter"),
+            RenderMode::Yuv => write!(f, "Draw on decoded YUV video"),
+        }
+    }
 }
 
 impl Default for RenderSettings {
@@ -29,6 +126,18 @@ impl Default for RenderSettings {
             upscale: false,
             use_chroma_key: false,
             chroma_key: [1.0 / 255.0, 177.0 / 255.0, 64.0 / 255.0],
+            render_osd: true,
+            render_srt: true,
+            render_images: true,
+            render_texts: true,
+            image_layers: Vec::new(),
+            text_layers: Vec::new(),
+            layer_order: LayerKind::DEFAULT_ORDER.to_vec(),
+            glyph_scaling_filter: ScalingFilter::default(),
+            blend_mode: BlendMode::default(),
+            render_mode: RenderMode::default(),
+            use_transparent_export: false,
+            transparent_format: TransparentFormat::default(),
         }
     }
 }
@@ -0,0 +1,36 @@
+use std::path::PathBuf;
+
+use super::font_file::FontFile;
+
+const TEXT_FONT_NAME: &str = "Text only (bundled)";
+const TEXT_FONT_BYTES: &[u8] = include_bytes!("../../../resources/osd_fonts/text_ultra.png");
+
+impl FontFile {
+    /// Get the bundled text font. It only contains letters, digits and punctuation at their ASCII code points, the
+    /// firmware specific symbols are drawn blank. See `resources/osd_fonts/README.md`.
+    pub fn bundled_text() -> Self {
+        let mut font_file =
+            FontFile::from_bytes(PathBuf::from(TEXT_FONT_NAME), TEXT_FONT_BYTES).expect("Bundled OSD font is invalid");
+        font_file.bundled = true;
+        font_file
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::{CharacterSize, FontType, ScalingFilter};
+
+    #[test]
+    fn load_bundled_text_font() {
+        let font_file = FontFile::bundled_text();
+        assert!(font_file.bundled);
+        assert_eq!(font_file.character_size, CharacterSize::Ultra);
+        assert_eq!(font_file.font_type, FontType::Standard);
+        assert_eq!(font_file.character_count, 256);
+
+        let characters = font_file.characters(&CharacterSize::Ultra, ScalingFilter::default());
+        assert!(characters[b'A' as usize].pixels().any(|p| p.0[3] == 255));
+        assert!(characters[b' ' as usize].pixels().all(|p| p.0[3] == 0));
+    }
+}
@@ -13,16 +13,37 @@ pub(crate) const CHARACTER_HEIGHT_SMALL: u32 = 36;
 pub(crate) const CHARACTER_WIDTH_RACE: u32 = 18;
 pub(crate) const CHARACTER_HEIGHT_RACE: u32 = 27;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub enum CharacterSize {
+    #[default]
     Large,
     Small,
     Ultra,
     XLarge,
     Race,
+    Custom {
+        width: u32,
+        height: u32,
+    },
 }
 
 impl CharacterSize {
+    pub const STANDARD: [CharacterSize; 5] = [
+        CharacterSize::Race,
+        CharacterSize::Small,
+        CharacterSize::Large,
+        CharacterSize::XLarge,
+        CharacterSize::Ultra,
+    ];
+
+    /// Get the character size with the given dimensions, preferring the standard sizes over a custom size.
+    pub fn from_dimensions(width: u32, height: u32) -> Self {
+        Self::STANDARD
+            .into_iter()
+            .find(|size| size.width() == width && size.height() == height)
+            .unwrap_or(CharacterSize::Custom { width, height })
+    }
+
     pub const fn width(&self) -> u32 {
         match self {
             CharacterSize::Large => CHARACTER_WIDTH_LARGE,
@@ -30,6 +51,7 @@ impl CharacterSize {
             CharacterSize::XLarge => CHARACTER_WIDTH_2K,
             CharacterSize::Ultra => CHARACTER_WIDTH_4K,
             CharacterSize::Race => CHARACTER_WIDTH_RACE,
+            CharacterSize::Custom { width, .. } => *width,
         }
     }
 
@@ -40,23 +62,21 @@ impl CharacterSize {
             CharacterSize::XLarge => CHARACTER_HEIGHT_2K,
             CharacterSize::Ultra => CHARACTER_HEIGHT_4K,
             CharacterSize::Race => CHARACTER_HEIGHT_RACE,
+            CharacterSize::Custom { height, .. } => *height,
         }
     }
 }
 
 impl Display for CharacterSize {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                CharacterSize::Large => "1080p",
-                CharacterSize::Small => "720p",
-                CharacterSize::XLarge => "2.7K",
-                CharacterSize::Ultra => "4K",
-                CharacterSize::Race => "540p",
-            }
-        )
+        match self {
+            CharacterSize::Large => write!(f, "1080p"),
+            CharacterSize::Small => write!(f, "720p"),
+            CharacterSize::XLarge => write!(f, "2.7K"),
+            CharacterSize::Ultra => write!(f, "4K"),
+            CharacterSize::Race => write!(f, "540p"),
+            CharacterSize::Custom { width, height } => write!(f, "{width}x{height}"),
+        }
     }
 }
 
@@ -79,6 +99,52 @@ impl FontType {
     }
 }
 
+impl FontType {
+    /// Font type of a font with the given number of pages. Four pages are interpreted as pages and not as colors.
+    pub fn from_pages(pages: u32) -> Option<Self> {
+        match pages {
+            1 => Some(FontType::Standard),
+            2 => Some(FontType::TwoPages),
+            3 => Some(FontType::ThreePages),
+            _ => None,
+        }
+    }
+}
+
+/// Number of characters per row in grid layout fonts.
+pub(crate) const GRID_COLUMNS: u32 = 16;
+
+/// How the characters are arranged in the font image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontLayout {
+    /// Every page is a single column of characters.
+    Strip,
+    /// Rows of 16 characters, used by WTFOS and HDZero fonts.
+    Grid,
+}
+
+/// Detect the dimensions of a strip or grid layout font.
+pub fn detect_layout(width: u32, height: u32) -> Result<(CharacterSize, FontType, u32, FontLayout), FontFileError> {
+    match detect_dimensions(width, height) {
+        Ok((size, font_type, character_count)) => Ok((size, font_type, character_count, FontLayout::Strip)),
+        Err(error) => detect_grid_dimensions(width, height)
+            .map(|(size, character_count)| (size, FontType::Standard, character_count, FontLayout::Grid))
+            .ok_or(error),
+    }
+}
+
+fn detect_grid_dimensions(width: u32, height: u32) -> Option<(CharacterSize, u32)> {
+    CharacterSize::STANDARD.into_iter().find_map(|size| {
+        let rows = height / size.height();
+        let character_count = rows * GRID_COLUMNS;
+        (width == size.width() * GRID_COLUMNS
+            && height.is_multiple_of(size.height())
+            && character_count > 0
+            && character_count.is_multiple_of(256))
+        .then_some((size, character_count))
+    })
+}
+
 pub fn detect_dimensions(width: u32, height: u32) -> Result<(CharacterSize, FontType, u32), FontFileError> {
     let (size, r#type) = if width == CHARACTER_WIDTH_SMALL && (height / CHARACTER_HEIGHT_SMALL).is_multiple_of(256) {
         (CharacterSize::Small, FontType::Standard)
@@ -139,6 +205,28 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn detect_grid_layouts() {
+        let test_cases = [
+            (384, 576, CharacterSize::Small, 256),
+            (384, 1152, CharacterSize::Small, 512),
+            (576, 864, CharacterSize::Large, 256),
+            (288, 432, CharacterSize::Race, 256),
+        ];
+        for (width, height, size, count) in test_cases {
+            assert_ok_eq!(
+                detect_layout(width, height),
+                (size, FontType::Standard, count, FontLayout::Grid)
+            );
+        }
+        // Strip layouts take precedence
+        assert_ok_eq!(
+            detect_layout(36, 13824),
+            (CharacterSize::Large, FontType::Standard, 256, FontLayout::Strip)
+        );
+        assert_err!(detect_layout(384, 36 * 8));
+    }
+
     #[test]
     fn detect_valid_font_sizes() {
         let test_cases = [
@@ -24,4 +24,31 @@ pub enum FontFileError {
 
     #[error("Invalid fond file height {height}")]
     InvalidFontFileHeight { height: u32 },
+
+    #[error("Invalid MCM font file, the file does not start with a MAX7456 header")]
+    InvalidMcmHeader,
+
+    #[error("Invalid MCM font file, line {line} is not an 8-bit binary value")]
+    InvalidMcmLine { line: usize },
+
+    #[error("The pages of the font have different sizes or layouts")]
+    MismatchedFontPages,
+
+    #[error("Glyph override for character {index} is outside of the font")]
+    GlyphOverrideOutOfRange { index: u32 },
+
+    #[error("Unable to load glyph override for character {index}, source: {source}")]
+    InvalidGlyphOverride { index: u32, source: image::ImageError },
+
+    #[error("Failed to save font file, source: {source}")]
+    FailedToSave { source: image::ImageError },
+}
+
+#[derive(Error, Debug)]
+pub enum FontLibraryError {
+    #[error("Unable to read font library directory, source: {source}")]
+    UnableToReadDirectory {
+        #[from]
+        source: std::io::Error,
+    },
 }
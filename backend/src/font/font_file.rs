@@ -1,12 +1,54 @@
-use std::path::PathBuf;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
 
 use derivative::Derivative;
-use image::{imageops::FilterType, io::Reader, DynamicImage, GenericImageView, ImageBuffer, Rgba, RgbaImage};
+use image::{
+    imageops::overlay, io::Reader, DynamicImage, GenericImage, GenericImageView, ImageBuffer, Rgba, RgbaImage,
+};
 
 use super::{
-    dimensions::{detect_dimensions, CharacterSize, FontType},
+    dimensions::{detect_layout, CharacterSize, FontLayout, FontType, GRID_COLUMNS},
     error::FontFileError,
+    mcm::{decode_mcm, MCM_HEADER},
+    scaling::{scale_character, ScalingFilter},
 };
+use crate::overlay::{GlyphEffects, StyledCharacters};
+
+/// Size MCM characters are stored at. It is an integer multiple of the 12x18 MCM characters so it can be downscaled to
+/// the other sizes with nearest neighbor filtering without distorting the pixel art, and glyph overrides keep their
+/// detail.
+pub(crate) const MCM_CHARACTER_SIZE: CharacterSize = CharacterSize::Ultra;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FontFormat {
+    Png,
+    Mcm,
+}
+
+impl FontFormat {
+    /// Filter used to scale characters of this format. MCM fonts are pixel art, so they are never scaled with a
+    /// smoothing filter.
+    fn scaling_filter(&self, filter: ScalingFilter) -> ScalingFilter {
+        match (self, filter) {
+            (FontFormat::Mcm, ScalingFilter::Bilinear | ScalingFilter::Lanczos) => ScalingFilter::Nearest,
+            _ => filter,
+        }
+    }
+}
+
+impl Display for FontFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FontFormat::Png => write!(f, "PNG"),
+            FontFormat::Mcm => write!(f, "MAX7456 (12x18)"),
+        }
+    }
+}
 
 #[derive(Derivative, Clone)]
 #[derivative(Debug)]
@@ -15,36 +57,285 @@ pub struct FontFile {
     pub character_count: u32,
     pub character_size: CharacterSize,
     pub font_type: FontType,
+    pub format: FontFormat,
+    /// The font is the bundled text font, see [`FontFile::bundled_text`].
+    pub bundled: bool,
+    /// The font image has an alpha channel. Without it the characters are drawn as opaque rectangles.
+    pub has_alpha: bool,
+    /// Characters as loaded from the font, without glyph overrides.
+    #[derivative(Debug = "ignore")]
+    original_characters: Arc<Vec<RgbaImage>>,
     #[derivative(Debug = "ignore")]
-    characters: Vec<RgbaImage>,
+    characters: Arc<Vec<RgbaImage>>,
+    /// Characters scaled to the sizes and with the filters that have been requested so far. Shared between clones of
+    /// the font file so every size is only scaled once, also when frames are rendered on multiple threads.
+    #[derivative(Debug = "ignore")]
+    scaled_characters: ScaledCharacters,
+    /// Scaled characters with glyph effects. Only the most recently used effects are kept for every size, so adjusting
+    /// the effects in the preview doesn't accumulate styled characters.
+    #[derivative(Debug = "ignore")]
+    styled_characters: StyledCharactersCache,
 }
 
+type ScaledCharacters = Arc<RwLock<HashMap<(CharacterSize, ScalingFilter), Arc<Vec<RgbaImage>>>>>;
+type StyledCharactersCache = Arc<RwLock<HashMap<(CharacterSize, ScalingFilter), Arc<StyledCharacters>>>>;
+
 impl FontFile {
     #[tracing::instrument(ret, err)]
     pub fn open(path: PathBuf) -> Result<Self, FontFileError> {
-        let font_image = Reader::open(&path)?.decode()?;
+        let page_paths = page_file_paths(&path);
+        if page_paths.len() > 1 {
+            if pages_match(&page_paths) {
+                return Self::open_pages(page_paths);
+            }
+            tracing::warn!("Font pages {:?} don't match, only opening {:?}", page_paths, path);
+        }
+
+        let bytes = fs::read(&path)?;
+        Self::from_bytes(path, &bytes)
+    }
+
+    /// Open a font that is split over multiple files with one page per file, like `font.png` and `font_2.png`.
+    fn open_pages(page_paths: Vec<PathBuf>) -> Result<Self, FontFileError> {
+        let mut pages = page_paths
+            .iter()
+            .map(|path| {
+                let font_image = Reader::open(path)?.decode()?;
+                let (width, height) = font_image.dimensions();
+                let (character_size, font_type, character_count, layout) = detect_layout(width, height)?;
+                if font_type != FontType::Standard {
+                    return Err(FontFileError::MismatchedFontPages);
+                }
+                let characters = split_characters(&font_image, &character_size, &font_type, character_count, layout);
+                Ok((
+                    character_size,
+                    character_count,
+                    font_image.color().has_alpha(),
+                    characters,
+                ))
+            })
+            .collect::<Result<Vec<_>, FontFileError>>()?;
+
+        let (character_size, character_count) = (pages[0].0.clone(), pages[0].1);
+        if pages
+            .iter()
+            .any(|(size, count, _, _)| *size != character_size || *count != character_count)
+        {
+            return Err(FontFileError::MismatchedFontPages);
+        }
+        let font_type = FontType::from_pages(pages.len() as u32).ok_or(FontFileError::MismatchedFontPages)?;
+        let has_alpha = pages.iter().all(|(_, _, has_alpha, _)| *has_alpha);
+        let characters = pages
+            .iter_mut()
+            .flat_map(|(_, _, _, characters)| std::mem::take(characters))
+            .collect();
+
+        let mut font_file = Self::new(
+            page_paths[0].clone(),
+            character_count,
+            character_size,
+            font_type,
+            FontFormat::Png,
+            characters,
+        );
+        font_file.has_alpha = has_alpha;
+        Ok(font_file)
+    }
+
+    /// Decode a PNG or MCM font from memory. `file_path` is only used to identify the font.
+    pub fn from_bytes(file_path: PathBuf, bytes: &[u8]) -> Result<Self, FontFileError> {
+        if bytes.trim_ascii_start().starts_with(MCM_HEADER.as_bytes()) {
+            let characters = decode_mcm(&String::from_utf8_lossy(bytes), &MCM_CHARACTER_SIZE)?;
+            return Ok(Self::new(
+                file_path,
+                characters.len() as u32,
+                MCM_CHARACTER_SIZE,
+                FontType::Standard,
+                FontFormat::Mcm,
+                characters,
+            ));
+        }
+
+        let font_image = image::load_from_memory(bytes)?;
         let (width, height) = font_image.dimensions();
-        let (character_size, font_type, character_count) = detect_dimensions(width, height)?;
+        let (character_size, font_type, character_count, layout) = detect_layout(width, height)?;
 
-        let characters = split_characters(&font_image, &character_size, &font_type, character_count);
+        let characters = split_characters(&font_image, &character_size, &font_type, character_count, layout);
 
-        Ok(Self {
-            file_path: path,
+        let mut font_file = Self::new(
+            file_path,
             character_count,
             character_size,
             font_type,
+            FontFormat::Png,
             characters,
-        })
+        );
+        font_file.has_alpha = font_image.color().has_alpha();
+        Ok(font_file)
     }
 
-    pub fn get_character(&self, index: usize, size: &CharacterSize) -> Option<ImageBuffer<Rgba<u8>, Vec<u8>>> {
-        self.characters.get(index).map(|original_image| {
-            if size.width() != self.character_size.width() || size.height() != self.character_size.height() {
-                image::imageops::resize(original_image, size.width(), size.height(), FilterType::Lanczos3)
-            } else {
-                original_image.clone()
+    fn new(
+        file_path: PathBuf,
+        character_count: u32,
+        character_size: CharacterSize,
+        font_type: FontType,
+        format: FontFormat,
+        characters: Vec<RgbaImage>,
+    ) -> Self {
+        let characters = Arc::new(characters);
+        Self {
+            file_path,
+            character_count,
+            scaled_characters: ScaledCharacters::default(),
+            styled_characters: StyledCharactersCache::default(),
+            character_size,
+            font_type,
+            format,
+            bundled: false,
+            has_alpha: true,
+            original_characters: characters.clone(),
+            characters,
+        }
+    }
+
+    /// Composite override images over characters of the font. The keys of `overrides` are indices of characters in
+    /// the font, see [`FontFile::character_index`]. Overrides that were applied before are replaced. The font is not
+    /// changed if one of the override images can't be loaded.
+    #[tracing::instrument(skip(self), err)]
+    pub fn apply_overrides(&mut self, overrides: &BTreeMap<u32, PathBuf>) -> Result<(), FontFileError> {
+        let override_images = overrides
+            .iter()
+            .map(|(&index, path)| {
+                if index as usize >= self.original_characters.len() {
+                    return Err(FontFileError::GlyphOverrideOutOfRange { index });
+                }
+                let image = image::open(path)
+                    .map_err(|source| FontFileError::InvalidGlyphOverride { index, source })?
+                    .to_rgba8();
+                Ok((
+                    index as usize,
+                    scale_character(
+                        &image,
+                        &self.character_size,
+                        self.format.scaling_filter(ScalingFilter::default()),
+                    ),
+                ))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut characters = self.original_characters.as_ref().clone();
+        for (index, image) in override_images {
+            overlay(&mut characters[index], &image, 0, 0);
+        }
+
+        self.characters = Arc::new(characters);
+        self.scaled_characters = ScaledCharacters::default();
+        self.styled_characters = StyledCharactersCache::default();
+        Ok(())
+    }
+
+    /// Save the font, including glyph overrides, as a PNG font with characters of the given size.
+    #[tracing::instrument(skip(self), err)]
+    pub fn save_png(&self, path: &Path, size: &CharacterSize, filter: ScalingFilter) -> Result<(), FontFileError> {
+        let characters_per_page = self.character_count;
+        let mut font_image = RgbaImage::new(
+            size.width() * self.font_type.pages(),
+            size.height() * characters_per_page,
+        );
+        for (index, character) in self.characters(size, filter).iter().enumerate() {
+            let page = index as u32 / characters_per_page;
+            let row = index as u32 % characters_per_page;
+            font_image
+                .copy_from(character, page * size.width(), row * size.height())
+                .map_err(|source| FontFileError::FailedToSave { source })?;
+        }
+        font_image
+            .save(path)
+            .map_err(|source| FontFileError::FailedToSave { source })
+    }
+
+    /// Get the position in [`FontFile::characters`] of the character for an OSD glyph index.
+    ///
+    /// The pages of a font are stored as columns in the font image and every column holds `character_count`
+    /// characters, so glyph indices that don't fit in the first column select a later page. The columns of a four
+    /// color font contain the same characters in different colors, so `color_page` selects the column instead.
+    pub fn character_index(&self, glyph_index: u16, color_page: u32) -> Option<usize> {
+        let characters_per_page = self.character_count;
+        let glyph_index = glyph_index as u32;
+
+        let (page, character) = match self.font_type {
+            FontType::FourColor => (color_page, glyph_index),
+            _ => (glyph_index / characters_per_page, glyph_index % characters_per_page),
+        };
+
+        (page < self.font_type.pages() && character < characters_per_page)
+            .then_some((page * characters_per_page + character) as usize)
+    }
+
+    /// Get the OSD glyph index and the page of a character in [`FontFile::characters`], the inverse of
+    /// [`FontFile::character_index`]. For four color fonts the page is the color page.
+    pub fn glyph_index(&self, character_index: usize) -> Option<(u16, u32)> {
+        let characters_per_page = self.character_count as usize;
+        let page = (character_index / characters_per_page) as u32;
+        let glyph_index = match self.font_type {
+            FontType::FourColor => character_index % characters_per_page,
+            _ => character_index,
+        };
+
+        if page >= self.font_type.pages() {
+            return None;
+        }
+        Some((u16::try_from(glyph_index).ok()?, page))
+    }
+
+    /// All characters scaled to `size` with `filter`. The characters are scaled the first time a size is requested and
+    /// cached for subsequent calls. MCM fonts use nearest neighbor filtering instead of a smoothing filter.
+    pub fn characters(&self, size: &CharacterSize, filter: ScalingFilter) -> Arc<Vec<RgbaImage>> {
+        if *size == self.character_size {
+            return self.characters.clone();
+        }
+
+        let filter = self.format.scaling_filter(filter);
+
+        let key = (size.clone(), filter);
+        if let Some(characters) = self.scaled_characters.read().unwrap().get(&key) {
+            return characters.clone();
+        }
+
+        // Hold the write lock while scaling so concurrent callers wait for the result instead of scaling again. The
+        // characters are scaled on this thread because a rayon worker that waits for a parallel iterator while holding
+        // the lock can pick up a frame compositing job that blocks on the same lock.
+        let mut scaled_characters = self.scaled_characters.write().unwrap();
+        scaled_characters
+            .entry(key)
+            .or_insert_with(|| {
+                Arc::new(
+                    self.characters
+                        .iter()
+                        .map(|character| scale_character(character, size, filter))
+                        .collect(),
+                )
+            })
+            .clone()
+    }
+
+    /// All characters scaled to `size` with `filter` and with `effects` applied.
+    pub fn styled_characters(
+        &self,
+        size: &CharacterSize,
+        filter: ScalingFilter,
+        effects: &GlyphEffects,
+    ) -> Arc<StyledCharacters> {
+        let key = (size.clone(), self.format.scaling_filter(filter));
+        if let Some(characters) = self.styled_characters.read().unwrap().get(&key) {
+            if characters.effects() == effects {
+                return characters.clone();
             }
-        })
+        }
+
+        let styled = Arc::new(StyledCharacters::new(self.characters(size, filter), effects.clone()));
+        self.styled_characters.write().unwrap().insert(key, styled.clone());
+        styled
     }
 }
 
@@ -53,6 +344,7 @@ fn split_characters(
     character_size: &CharacterSize,
     font_type: &FontType,
     character_count: u32,
+    layout: FontLayout,
 ) -> Vec<ImageBuffer<Rgba<u8>, Vec<u8>>> {
     let pages = font_type.pages();
     let char_width = character_size.width();
@@ -61,9 +353,14 @@ fn split_characters(
     let mut char_vec = Vec::with_capacity((character_count * pages) as usize);
 
     for page_idx in 0..pages {
-        let x = page_idx * char_width;
         for char_idx in 0..character_count {
-            let y = char_idx * char_height;
+            let (x, y) = match layout {
+                FontLayout::Strip => (page_idx * char_width, char_idx * char_height),
+                FontLayout::Grid => (
+                    (char_idx % GRID_COLUMNS) * char_width,
+                    (char_idx / GRID_COLUMNS) * char_height,
+                ),
+            };
             let char = font_image.view(x, y, char_width, char_height).to_image();
             char_vec.push(char);
         }
@@ -71,3 +368,331 @@ fn split_characters(
 
     char_vec
 }
+
+/// Get the files of a font that is split over multiple files, starting with the first page. The second and third
+/// pages have a `_2` and `_3` suffix, e.g. `font_hd.png`, `font_hd_2.png`. Opening any of the page files opens the
+/// whole set.
+pub(crate) fn page_file_paths(path: &Path) -> Vec<PathBuf> {
+    let (Some(stem), Some(extension)) = (path.file_stem(), path.extension()) else {
+        return vec![path.to_path_buf()];
+    };
+    let stem = stem.to_string_lossy();
+    let extension = extension.to_string_lossy();
+    let base_stem = ["_2", "_3"]
+        .iter()
+        .find_map(|suffix| stem.strip_suffix(suffix))
+        .unwrap_or(&stem);
+
+    let base_path = path.with_file_name(format!("{base_stem}.{extension}"));
+    if !base_path.is_file() {
+        return vec![path.to_path_buf()];
+    }
+
+    std::iter::once(base_path)
+        .chain(
+            (2..=3)
+                .map(|page| path.with_file_name(format!("{base_stem}_{page}.{extension}")))
+                .take_while(|page_path| page_path.is_file()),
+        )
+        .collect()
+}
+
+/// Check that the page files of a split font are standard fonts with the same character size and layout, so a file
+/// with a `_2` suffix that is not a page of the same font is not merged with it. Only the image headers are read.
+fn pages_match(page_paths: &[PathBuf]) -> bool {
+    let layouts = page_paths
+        .iter()
+        .map(|path| {
+            let (width, height) = image::image_dimensions(path).ok()?;
+            detect_layout(width, height).ok()
+        })
+        .collect::<Option<Vec<_>>>();
+    let Some(layouts) = layouts else {
+        return false;
+    };
+    layouts
+        .iter()
+        .all(|(_, font_type, _, _)| *font_type == FontType::Standard)
+        && layouts.windows(2).all(|pair| pair[0] == pair[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_matches, assert_none, assert_ok, assert_some, assert_some_eq};
+
+    use super::*;
+
+    /// Writes a font image where every character is filled with its page in the red channel and its position in the
+    /// page in the green channel.
+    fn synthesize_font_image(name: &str, pages: u32, characters_per_page: u32) -> PathBuf {
+        let size = CharacterSize::Race;
+        let image = RgbaImage::from_fn(size.width() * pages, size.height() * characters_per_page, |x, y| {
+            let page = x / size.width();
+            let character = y / size.height();
+            Rgba([page as u8, character as u8, 0, 255])
+        });
+        let path = std::env::temp_dir().join(format!("walksnail-osd-tool-{name}.png"));
+        image.save(&path).unwrap();
+        path
+    }
+
+    fn open_synthesized_font(name: &str, pages: u32, characters_per_page: u32) -> FontFile {
+        let path = synthesize_font_image(name, pages, characters_per_page);
+        let font_file = FontFile::open(path.clone());
+        fs::remove_file(path).ok();
+        assert_ok!(font_file)
+    }
+
+    fn character_color(font_file: &FontFile, glyph_index: u16, color_page: u32) -> Option<(u8, u8)> {
+        font_file.character_index(glyph_index, color_page).map(|index| {
+            let pixel = font_file.characters[index].get_pixel(0, 0);
+            (pixel.0[0], pixel.0[1])
+        })
+    }
+
+    #[test]
+    fn address_pages_by_glyph_index() {
+        let font_file = open_synthesized_font("three-pages", 3, 256);
+        assert_eq!(font_file.font_type, FontType::ThreePages);
+
+        assert_some_eq!(character_color(&font_file, 65, 0), (0, 65));
+        assert_some_eq!(character_color(&font_file, 256 + 65, 0), (1, 65));
+        assert_some_eq!(character_color(&font_file, 512 + 255, 0), (2, 255));
+        assert_none!(character_color(&font_file, 768, 0));
+        // The color page only applies to four color fonts
+        assert_some_eq!(character_color(&font_file, 65, 2), (0, 65));
+    }
+
+    #[test]
+    fn address_tall_single_page_font() {
+        let font_file = open_synthesized_font("tall-single-page", 1, 512);
+        assert_eq!(font_file.font_type, FontType::Standard);
+        assert_eq!(font_file.character_count, 512);
+
+        assert_some_eq!(character_color(&font_file, 300, 0), (0, 300u32 as u8));
+        assert_none!(character_color(&font_file, 512, 0));
+    }
+
+    #[test]
+    fn address_four_color_pages() {
+        let font_file = open_synthesized_font("four-color", 4, 256);
+        assert_eq!(font_file.font_type, FontType::FourColor);
+
+        assert_some_eq!(character_color(&font_file, 65, 0), (0, 65));
+        assert_some_eq!(character_color(&font_file, 65, 3), (3, 65));
+        assert_none!(character_color(&font_file, 65, 4));
+        assert_none!(character_color(&font_file, 256, 0));
+    }
+
+    #[test]
+    fn glyph_index_is_inverse_of_character_index() {
+        for (name, pages) in [("inverse-three-pages", 3), ("inverse-four-color", 4)] {
+            let font_file = open_synthesized_font(name, pages, 256);
+            for character_index in 0..font_file.characters.len() {
+                let (glyph_index, page) = assert_some!(font_file.glyph_index(character_index));
+                assert_some_eq!(font_file.character_index(glyph_index, page), character_index);
+            }
+            assert_none!(font_file.glyph_index(font_file.characters.len()));
+        }
+
+        let four_color = open_synthesized_font("inverse-four-color-page", 4, 256);
+        assert_some_eq!(four_color.glyph_index(2 * 256 + 65), (65, 2));
+    }
+
+    fn font_file() -> FontFile {
+        let characters = (0..4u8)
+            .map(|i| RgbaImage::from_pixel(36, 54, Rgba([i, i, i, 255])))
+            .collect();
+        FontFile::new(
+            PathBuf::from("font.png"),
+            4,
+            CharacterSize::Large,
+            FontType::Standard,
+            FontFormat::Png,
+            characters,
+        )
+    }
+
+    #[test]
+    fn open_grid_layout_font() {
+        let size = CharacterSize::Small;
+        let image = RgbaImage::from_fn(size.width() * 16, size.height() * 32, |x, y| {
+            let character = (y / size.height()) * 16 + x / size.width();
+            Rgba([(character / 256) as u8, character as u8, 0, 255])
+        });
+        let path = std::env::temp_dir().join("walksnail-osd-tool-grid.png");
+        image.save(&path).unwrap();
+        let font_file = FontFile::open(path.clone());
+        fs::remove_file(path).ok();
+
+        let font_file = assert_ok!(font_file);
+        assert_eq!(font_file.character_size, CharacterSize::Small);
+        assert_eq!(font_file.font_type, FontType::Standard);
+        assert_eq!(font_file.character_count, 512);
+        assert_some_eq!(character_color(&font_file, 17, 0), (0, 17));
+        assert_some_eq!(character_color(&font_file, 300, 0), (1, 300u32 as u8));
+    }
+
+    #[test]
+    fn open_split_font_files() {
+        let directory = std::env::temp_dir().join("walksnail-osd-tool-split-font");
+        fs::create_dir_all(&directory).unwrap();
+        let size = CharacterSize::Race;
+        for (page, file_name) in [(0u8, "font_hd.png"), (1, "font_hd_2.png")] {
+            RgbaImage::from_fn(size.width(), size.height() * 256, |_, y| {
+                Rgba([page, (y / size.height()) as u8, 0, 255])
+            })
+            .save(directory.join(file_name))
+            .unwrap();
+        }
+
+        let from_first_page = FontFile::open(directory.join("font_hd.png"));
+        let from_second_page = FontFile::open(directory.join("font_hd_2.png"));
+        fs::remove_dir_all(directory).ok();
+
+        for font_file in [assert_ok!(from_first_page), assert_ok!(from_second_page)] {
+            assert_eq!(font_file.file_path.file_name().unwrap(), "font_hd.png");
+            assert_eq!(font_file.font_type, FontType::TwoPages);
+            assert_eq!(font_file.character_count, 256);
+            assert_some_eq!(character_color(&font_file, 65, 0), (0, 65));
+            assert_some_eq!(character_color(&font_file, 256 + 65, 0), (1, 65));
+        }
+    }
+
+    #[test]
+    fn open_single_file_when_pages_do_not_match() {
+        let directory = std::env::temp_dir().join("walksnail-osd-tool-mismatched-pages");
+        fs::create_dir_all(&directory).unwrap();
+        let size = CharacterSize::Race;
+        RgbaImage::from_fn(size.width(), size.height() * 256, |_, y| {
+            Rgba([0, (y / size.height()) as u8, 0, 255])
+        })
+        .save(directory.join("font_hd.png"))
+        .unwrap();
+        // A font with another character size that only happens to have the suffix
+        let other_size = CharacterSize::Small;
+        RgbaImage::from_pixel(other_size.width(), other_size.height() * 256, Rgba([1, 0, 0, 255]))
+            .save(directory.join("font_hd_2.png"))
+            .unwrap();
+
+        let from_first_page = FontFile::open(directory.join("font_hd.png"));
+        let from_second_page = FontFile::open(directory.join("font_hd_2.png"));
+        fs::remove_dir_all(directory).ok();
+
+        let font_file = assert_ok!(from_first_page);
+        assert_eq!(font_file.file_path.file_name().unwrap(), "font_hd.png");
+        assert_eq!(font_file.font_type, FontType::Standard);
+        assert_eq!(font_file.character_size, CharacterSize::Race);
+        assert_some_eq!(character_color(&font_file, 65, 0), (0, 65));
+
+        let font_file = assert_ok!(from_second_page);
+        assert_eq!(font_file.file_path.file_name().unwrap(), "font_hd_2.png");
+        assert_eq!(font_file.font_type, FontType::Standard);
+        assert_eq!(font_file.character_size, CharacterSize::Small);
+    }
+
+    #[test]
+    fn apply_glyph_overrides() {
+        let mut font_file = open_synthesized_font("overrides", 2, 256);
+        let override_path = std::env::temp_dir().join("walksnail-osd-tool-override.png");
+        // Half size override that should be scaled to the character size
+        RgbaImage::from_pixel(9, 14, Rgba([200, 100, 50, 255]))
+            .save(&override_path)
+            .unwrap();
+
+        let overrides = BTreeMap::from([(300, override_path.clone())]);
+        let result = font_file.apply_overrides(&overrides);
+        assert_ok!(result);
+        assert_some_eq!(character_color(&font_file, 300, 0), (200, 100));
+        assert_some_eq!(character_color(&font_file, 299, 0), (1, 43));
+
+        // Scaled characters are not cached from before the override was applied
+        let scaled = font_file.characters(&CharacterSize::Small, ScalingFilter::Lanczos);
+        assert_eq!(*scaled[300].get_pixel(12, 18), Rgba([200, 100, 50, 255]));
+
+        // Applying an empty set of overrides restores the original characters
+        assert_ok!(font_file.apply_overrides(&BTreeMap::new()));
+        assert_some_eq!(character_color(&font_file, 300, 0), (1, 44));
+
+        let invalid = BTreeMap::from([(512, override_path.clone())]);
+        assert_matches!(
+            font_file.apply_overrides(&invalid),
+            Err(FontFileError::GlyphOverrideOutOfRange { index: 512 })
+        );
+        let missing = BTreeMap::from([(5, PathBuf::from("this/override/does/not/exist.png"))]);
+        assert_matches!(
+            font_file.apply_overrides(&missing),
+            Err(FontFileError::InvalidGlyphOverride { index: 5, .. })
+        );
+
+        fs::remove_file(override_path).ok();
+    }
+
+    #[test]
+    fn mcm_characters_are_not_smoothed() {
+        // One character with white first four columns, the other pixels are transparent
+        let mut mcm = vec![MCM_HEADER.to_string()];
+        mcm.extend((0..64).map(|line| {
+            if line < 54 && line % 3 == 0 {
+                "10101010"
+            } else {
+                "01010101"
+            }
+            .to_string()
+        }));
+        let font_file = assert_ok!(FontFile::from_bytes(
+            PathBuf::from("font.mcm"),
+            mcm.join("\n").as_bytes()
+        ));
+        assert_eq!(font_file.character_size, MCM_CHARACTER_SIZE);
+
+        for size in [CharacterSize::Race, CharacterSize::Custom { width: 31, height: 47 }] {
+            let characters = font_file.characters(&size, ScalingFilter::Lanczos);
+            assert!(characters[0].pixels().all(|p| p.0[3] == 0 || p.0[3] == 255), "{size}");
+        }
+    }
+
+    #[test]
+    fn save_font_in_other_size() {
+        let font_file = open_synthesized_font("save-source", 2, 256);
+        let path = std::env::temp_dir().join("walksnail-osd-tool-saved-font.png");
+        assert_ok!(font_file.save_png(&path, &CharacterSize::Small, ScalingFilter::Nearest));
+        let saved = FontFile::open(path.clone());
+        fs::remove_file(path).ok();
+
+        let saved = assert_ok!(saved);
+        assert_eq!(saved.character_size, CharacterSize::Small);
+        assert_eq!(saved.font_type, FontType::TwoPages);
+        assert_eq!(saved.character_count, 256);
+        assert_some_eq!(character_color(&saved, 256 + 7, 0), (1, 7));
+    }
+
+    #[test]
+    fn native_size_is_not_scaled() {
+        let font_file = font_file();
+        assert!(Arc::ptr_eq(
+            &font_file.characters(&CharacterSize::Large, ScalingFilter::Nearest),
+            &font_file.characters
+        ));
+    }
+
+    #[test]
+    fn scaled_characters_are_cached_across_clones() {
+        let font_file = font_file();
+        let scaled = font_file.characters(&CharacterSize::Small, ScalingFilter::Lanczos);
+        assert_eq!(scaled.len(), 4);
+        assert_eq!(scaled[3].dimensions(), (24, 36));
+        assert_eq!(*scaled[3].get_pixel(12, 18), Rgba([3, 3, 3, 255]));
+
+        let cloned = font_file.clone();
+        assert!(Arc::ptr_eq(
+            &scaled,
+            &cloned.characters(&CharacterSize::Small, ScalingFilter::Lanczos)
+        ));
+        // Every filter has its own cache entry
+        assert!(!Arc::ptr_eq(
+            &scaled,
+            &cloned.characters(&CharacterSize::Small, ScalingFilter::Nearest)
+        ));
+    }
+}
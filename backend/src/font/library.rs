@@ -0,0 +1,247 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    dimensions::{detect_layout, CharacterSize},
+    error::FontLibraryError,
+    font_file::{page_file_paths, FontFormat, MCM_CHARACTER_SIZE},
+};
+use crate::osd::FcFirmware;
+
+/// Directory of font files from which the font that matches the OSD file is selected.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FontLibrary {
+    pub directory: Option<PathBuf>,
+    /// Firmware the user assigned to fonts, by file name. Fonts without a tag use the firmware detected from the file
+    /// name.
+    pub firmware_tags: HashMap<String, FcFirmware>,
+    #[serde(skip)]
+    entries: Vec<FontLibraryEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontLibraryEntry {
+    pub file_path: PathBuf,
+    pub file_name: String,
+    pub format: FontFormat,
+    pub character_size: CharacterSize,
+    pub detected_firmware: Option<FcFirmware>,
+}
+
+impl FontLibrary {
+    /// Reads the font files in the library directory. Files that are not valid fonts are skipped.
+    #[tracing::instrument(skip(self), fields(directory = ?self.directory), err)]
+    pub fn scan(&mut self) -> Result<(), FontLibraryError> {
+        self.entries.clear();
+        let Some(directory) = &self.directory else {
+            return Ok(());
+        };
+
+        let mut paths = fs::read_dir(directory)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        self.entries = paths.iter().filter_map(|path| read_entry(path)).collect();
+        tracing::info!("Found {} fonts in library", self.entries.len());
+        Ok(())
+    }
+
+    pub fn entries(&self) -> &[FontLibraryEntry] {
+        &self.entries
+    }
+
+    /// Firmware of the font, either tagged by the user or detected from the file name.
+    pub fn firmware(&self, entry: &FontLibraryEntry) -> Option<FcFirmware> {
+        self.firmware_tags
+            .get(&entry.file_name)
+            .copied()
+            .or(entry.detected_firmware)
+    }
+
+    /// Tag a font with a firmware, or remove the tag to use the detected firmware.
+    pub fn set_firmware_tag(&mut self, file_name: &str, firmware: Option<FcFirmware>) {
+        match firmware {
+            Some(firmware) => self.firmware_tags.insert(file_name.to_string(), firmware),
+            None => self.firmware_tags.remove(file_name),
+        };
+    }
+
+    /// Select the font for the firmware with a character size closest to `character_size`. Larger fonts are preferred
+    /// over smaller fonts because downscaling gives better results than upscaling. Fonts without firmware are used
+    /// when no font for the firmware is in the library.
+    pub fn select(&self, firmware: FcFirmware, character_size: &CharacterSize) -> Option<&FontLibraryEntry> {
+        let for_firmware = self
+            .entries
+            .iter()
+            .filter(|entry| self.firmware(entry) == Some(firmware))
+            .collect::<Vec<_>>();
+        let candidates = if for_firmware.is_empty() {
+            self.entries
+                .iter()
+                .filter(|entry| self.firmware(entry).is_none())
+                .collect()
+        } else {
+            for_firmware
+        };
+
+        let target_height = character_size.height();
+        candidates.into_iter().min_by_key(|entry| {
+            let height = entry.character_size.height();
+            if height >= target_height {
+                (height != target_height, height - target_height)
+            } else {
+                (true, u32::MAX - height)
+            }
+        })
+    }
+}
+
+fn read_entry(path: &Path) -> Option<FontLibraryEntry> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    let (format, character_size) = match extension.as_str() {
+        "png" => {
+            // Later pages of split fonts are opened together with the first page
+            if page_file_paths(path)
+                .first()
+                .is_some_and(|first_page| first_page != path)
+            {
+                return None;
+            }
+            let (width, height) = image::image_dimensions(path).ok()?;
+            let (character_size, _, _, _) = detect_layout(width, height).ok()?;
+            (FontFormat::Png, character_size)
+        }
+        "mcm" => (FontFormat::Mcm, MCM_CHARACTER_SIZE),
+        _ => return None,
+    };
+    let file_name = path.file_name()?.to_string_lossy().to_string();
+
+    Some(FontLibraryEntry {
+        file_path: path.to_path_buf(),
+        detected_firmware: detect_firmware(&file_name),
+        file_name,
+        format,
+        character_size,
+    })
+}
+
+/// Guess the firmware of a font from the common firmware names and abbreviations in the file name.
+pub fn detect_firmware(file_name: &str) -> Option<FcFirmware> {
+    let name = file_name.to_lowercase();
+    if name.contains("betaflight") || name.contains("btfl") {
+        Some(FcFirmware::Betaflight)
+    } else if name.contains("inav") {
+        Some(FcFirmware::Inav)
+    } else if name.contains("ardu") {
+        Some(FcFirmware::ArduPilot)
+    } else if name.contains("kiss") && name.contains("ultra") {
+        Some(FcFirmware::KissUltra)
+    } else if name.contains("kiss") {
+        Some(FcFirmware::Kiss)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_none, assert_ok, assert_some, assert_some_eq};
+    use image::RgbaImage;
+
+    use super::*;
+
+    fn entry(file_name: &str, character_size: CharacterSize) -> FontLibraryEntry {
+        FontLibraryEntry {
+            file_path: PathBuf::from(file_name),
+            file_name: file_name.to_string(),
+            format: FontFormat::Png,
+            character_size,
+            detected_firmware: detect_firmware(file_name),
+        }
+    }
+
+    #[test]
+    fn detect_firmware_from_file_name() {
+        assert_some_eq!(detect_firmware("WS_BTFL_Nexus_36.png"), FcFirmware::Betaflight);
+        assert_some_eq!(detect_firmware("betaflight_font.png"), FcFirmware::Betaflight);
+        assert_some_eq!(detect_firmware("INAV_default_24.png"), FcFirmware::Inav);
+        assert_some_eq!(detect_firmware("ArduPilot.mcm"), FcFirmware::ArduPilot);
+        assert_some_eq!(detect_firmware("kiss_ultra.png"), FcFirmware::KissUltra);
+        assert_none!(detect_firmware("font.png"));
+    }
+
+    #[test]
+    fn select_font_by_firmware_and_size() {
+        let mut library = FontLibrary {
+            entries: vec![
+                entry("btfl_small.png", CharacterSize::Small),
+                entry("btfl_large.png", CharacterSize::Large),
+                entry("btfl_ultra.png", CharacterSize::Ultra),
+                entry("inav.png", CharacterSize::Large),
+                entry("generic.png", CharacterSize::Large),
+            ],
+            ..Default::default()
+        };
+
+        let select = |library: &FontLibrary, firmware, size| {
+            library.select(firmware, &size).map(|entry| entry.file_name.clone())
+        };
+
+        assert_some_eq!(
+            select(&library, FcFirmware::Betaflight, CharacterSize::Large),
+            "btfl_large.png"
+        );
+        assert_some_eq!(
+            select(&library, FcFirmware::Betaflight, CharacterSize::XLarge),
+            "btfl_ultra.png"
+        );
+        assert_some_eq!(
+            select(&library, FcFirmware::Betaflight, CharacterSize::Race),
+            "btfl_small.png"
+        );
+        assert_some_eq!(select(&library, FcFirmware::Inav, CharacterSize::Small), "inav.png");
+        assert_some_eq!(
+            select(&library, FcFirmware::ArduPilot, CharacterSize::Large),
+            "generic.png"
+        );
+
+        library.set_firmware_tag("generic.png", Some(FcFirmware::ArduPilot));
+        library.set_firmware_tag("inav.png", Some(FcFirmware::Betaflight));
+        assert_some_eq!(
+            select(&library, FcFirmware::ArduPilot, CharacterSize::Large),
+            "generic.png"
+        );
+        assert_none!(select(&library, FcFirmware::Inav, CharacterSize::Large));
+    }
+
+    #[test]
+    fn scan_directory() {
+        let directory = std::env::temp_dir().join("walksnail-osd-tool-font-library");
+        fs::create_dir_all(&directory).unwrap();
+        RgbaImage::new(18, 6912).save(directory.join("inav_race.png")).unwrap();
+        RgbaImage::new(18, 6912)
+            .save(directory.join("inav_race_2.png"))
+            .unwrap();
+        fs::write(directory.join("broken.png"), b"not an image").unwrap();
+        fs::write(directory.join("notes.txt"), b"not a font").unwrap();
+
+        let mut library = FontLibrary {
+            directory: Some(directory.clone()),
+            ..Default::default()
+        };
+        let result = library.scan();
+        fs::remove_dir_all(directory).ok();
+
+        assert_ok!(result);
+        assert_eq!(library.entries().len(), 1);
+        let entry = assert_some!(library.entries().first());
+        assert_eq!(entry.character_size, CharacterSize::Race);
+        assert_some_eq!(library.firmware(entry), FcFirmware::Inav);
+    }
+}
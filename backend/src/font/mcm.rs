@@ -0,0 +1,120 @@
+use image::{imageops::FilterType, Rgba, RgbaImage};
+
+use super::{dimensions::CharacterSize, error::FontFileError};
+
+const MCM_CHARACTER_WIDTH: u32 = 12;
+const MCM_CHARACTER_HEIGHT: u32 = 18;
+
+pub(crate) const MCM_HEADER: &str = "MAX7456";
+const LINES_PER_CHARACTER: usize = 64;
+const PIXELS_PER_BYTE: u32 = 4;
+const DATA_BYTES_PER_CHARACTER: usize = (MCM_CHARACTER_WIDTH * MCM_CHARACTER_HEIGHT / PIXELS_PER_BYTE) as usize;
+
+const BLACK: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const TRANSPARENT: Rgba<u8> = Rgba([0, 0, 0, 0]);
+
+/// Decodes a MAX7456 `.mcm` font and upscales the 12x18 characters to `size` with nearest neighbor filtering to keep
+/// the pixel art look.
+///
+/// Every character is stored as 64 lines of 8 binary digits of which the first 54 contain the pixel data. Each byte
+/// holds four 2-bit pixels: `00` is black, `10` is white and `01` or `11` is transparent.
+pub fn decode_mcm(text: &str, size: &CharacterSize) -> Result<Vec<RgbaImage>, FontFileError> {
+    let mut lines = text.lines().map(str::trim).filter(|l| !l.is_empty()).enumerate();
+
+    match lines.next() {
+        Some((_, header)) if header == MCM_HEADER => {}
+        _ => return Err(FontFileError::InvalidMcmHeader),
+    }
+
+    let bytes = lines
+        .map(|(line_number, line)| {
+            if line.len() != 8 {
+                return Err(FontFileError::InvalidMcmLine { line: line_number + 1 });
+            }
+            u8::from_str_radix(line, 2).map_err(|_| FontFileError::InvalidMcmLine { line: line_number + 1 })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let characters = bytes
+        .chunks_exact(LINES_PER_CHARACTER)
+        .map(|character_bytes| {
+            let character = decode_character(&character_bytes[..DATA_BYTES_PER_CHARACTER]);
+            image::imageops::resize(&character, size.width(), size.height(), FilterType::Nearest)
+        })
+        .collect::<Vec<_>>();
+
+    if characters.is_empty() {
+        return Err(FontFileError::InvalidMcmHeader);
+    }
+
+    Ok(characters)
+}
+
+fn decode_character(bytes: &[u8]) -> RgbaImage {
+    RgbaImage::from_fn(MCM_CHARACTER_WIDTH, MCM_CHARACTER_HEIGHT, |x, y| {
+        let pixel_idx = y * MCM_CHARACTER_WIDTH + x;
+        let byte = bytes[(pixel_idx / PIXELS_PER_BYTE) as usize];
+        let shift = 6 - 2 * (pixel_idx % PIXELS_PER_BYTE);
+        match (byte >> shift) & 0b11 {
+            0b00 => BLACK,
+            0b10 => WHITE,
+            _ => TRANSPARENT,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_matches, assert_ok};
+
+    use super::*;
+
+    /// Builds a MCM file where the first row of every character alternates black, white, transparent, transparent and
+    /// all other pixels are transparent.
+    fn mcm_file(character_count: usize) -> String {
+        let mut lines = vec![MCM_HEADER.to_string()];
+        for _ in 0..character_count {
+            for line_idx in 0..LINES_PER_CHARACTER {
+                let line = if line_idx < 3 { "00100111" } else { "01010101" };
+                lines.push(line.to_string());
+            }
+        }
+        lines.join("\r\n")
+    }
+
+    #[test]
+    fn decode_mcm_characters() {
+        let characters = assert_ok!(decode_mcm(&mcm_file(2), &CharacterSize::Small));
+        assert_eq!(characters.len(), 2);
+
+        let character = &characters[1];
+        assert_eq!(character.dimensions(), (24, 36));
+        // Every MCM pixel becomes a 2x2 block at this size
+        assert_eq!(*character.get_pixel(0, 0), BLACK);
+        assert_eq!(*character.get_pixel(1, 1), BLACK);
+        assert_eq!(*character.get_pixel(2, 0), WHITE);
+        assert_eq!(*character.get_pixel(3, 1), WHITE);
+        assert_eq!(*character.get_pixel(4, 0), TRANSPARENT);
+        assert_eq!(*character.get_pixel(6, 0), TRANSPARENT);
+        assert_eq!(*character.get_pixel(0, 2), TRANSPARENT);
+    }
+
+    #[test]
+    fn reject_missing_header() {
+        let file = mcm_file(1).replacen(MCM_HEADER, "MAX1234", 1);
+        assert_matches!(
+            decode_mcm(&file, &CharacterSize::Large),
+            Err(FontFileError::InvalidMcmHeader)
+        );
+    }
+
+    #[test]
+    fn reject_invalid_line() {
+        let file = mcm_file(1).replacen("01010101", "0101010", 1);
+        assert_matches!(
+            decode_mcm(&file, &CharacterSize::Large),
+            Err(FontFileError::InvalidMcmLine { line: 5 })
+        );
+    }
+}
@@ -1,7 +1,16 @@
+mod bundled;
 mod dimensions;
 mod error;
 mod font_file;
+mod library;
+mod mcm;
+mod scaling;
+mod validation;
 
 pub use dimensions::{CharacterSize, FontType};
-pub use error::FontFileError;
-pub use font_file::FontFile;
+pub use error::{FontFileError, FontLibraryError};
+pub use font_file::{FontFile, FontFormat};
+pub use library::{detect_firmware, FontLibrary, FontLibraryEntry};
+pub(crate) use scaling::scale_character;
+pub use scaling::ScalingFilter;
+pub use validation::FontValidationReport;
@@ -0,0 +1,137 @@
+use std::fmt::Display;
+
+use image::{imageops::FilterType, Rgba, Rgba32FImage, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+use super::dimensions::CharacterSize;
+
+/// Filter used to scale the font characters to the size of the OSD characters in the video.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ScalingFilter {
+    /// Keeps hard pixel edges, best for pixel art fonts.
+    Nearest,
+    /// Scales up by an integer factor without filtering and then scales down to the target size, which keeps edges
+    /// sharp without the uneven pixel sizes of nearest neighbor.
+    IntegerDownsample,
+    Bilinear,
+    #[default]
+    Lanczos,
+}
+
+impl ScalingFilter {
+    pub const ALL: [ScalingFilter; 4] = [
+        ScalingFilter::Nearest,
+        ScalingFilter::IntegerDownsample,
+        ScalingFilter::Bilinear,
+        ScalingFilter::Lanczos,
+    ];
+}
+
+impl Display for ScalingFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScalingFilter::Nearest => write!(f, "Nearest neighbor"),
+            ScalingFilter::IntegerDownsample => write!(f, "Integer scale and downsample"),
+            ScalingFilter::Bilinear => write!(f, "Bilinear"),
+            ScalingFilter::Lanczos => write!(f, "Lanczos"),
+        }
+    }
+}
+
+/// Scale a character to `size`. Sizes that are an exact multiple of the character size always use integer scaling
+/// so the scaled character is identical for all filters.
+pub fn scale_character(character: &RgbaImage, size: &CharacterSize, filter: ScalingFilter) -> RgbaImage {
+    let (width, height) = character.dimensions();
+    if (width, height) == (size.width(), size.height()) {
+        return character.clone();
+    }
+    if size.width().is_multiple_of(width) && size.height().is_multiple_of(height) {
+        return integer_scale(character, size.width() / width, size.height() / height);
+    }
+
+    match filter {
+        ScalingFilter::Nearest => image::imageops::resize(character, size.width(), size.height(), FilterType::Nearest),
+        ScalingFilter::IntegerDownsample => {
+            let upscaled = integer_scale(character, size.width().div_ceil(width), size.height().div_ceil(height));
+            resize_premultiplied(&upscaled, size, FilterType::Triangle)
+        }
+        ScalingFilter::Bilinear => resize_premultiplied(character, size, FilterType::Triangle),
+        ScalingFilter::Lanczos => resize_premultiplied(character, size, FilterType::Lanczos3),
+    }
+}
+
+fn integer_scale(character: &RgbaImage, factor_x: u32, factor_y: u32) -> RgbaImage {
+    RgbaImage::from_fn(character.width() * factor_x, character.height() * factor_y, |x, y| {
+        *character.get_pixel(x / factor_x, y / factor_y)
+    })
+}
+
+/// Resizes a character with premultiplied alpha so the color of transparent pixels doesn't bleed into the edges of the
+/// character.
+fn resize_premultiplied(character: &RgbaImage, size: &CharacterSize, filter: FilterType) -> RgbaImage {
+    let premultiplied = Rgba32FImage::from_fn(character.width(), character.height(), |x, y| {
+        let [r, g, b, a] = character.get_pixel(x, y).0.map(|c| c as f32 / 255.0);
+        Rgba([r * a, g * a, b * a, a])
+    });
+
+    let resized = image::imageops::resize(&premultiplied, size.width(), size.height(), filter);
+
+    RgbaImage::from_fn(size.width(), size.height(), |x, y| {
+        let [r, g, b, a] = resized.get_pixel(x, y).0;
+        let a = a.clamp(0.0, 1.0);
+        let unpremultiply = |c: f32| if a > 0.0 { (c / a).clamp(0.0, 1.0) } else { 0.0 };
+        Rgba([unpremultiply(r), unpremultiply(g), unpremultiply(b), a].map(|c| (c * 255.0).round() as u8))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// White left half, transparent black right half.
+    fn half_white_character() -> RgbaImage {
+        RgbaImage::from_fn(36, 54, |x, _| {
+            if x < 18 {
+                Rgba([255, 255, 255, 255])
+            } else {
+                Rgba([0, 0, 0, 0])
+            }
+        })
+    }
+
+    #[test]
+    fn transparent_pixels_dont_darken_edges() {
+        let size = CharacterSize::Custom { width: 25, height: 37 };
+        for filter in [
+            ScalingFilter::Bilinear,
+            ScalingFilter::Lanczos,
+            ScalingFilter::IntegerDownsample,
+        ] {
+            let scaled = scale_character(&half_white_character(), &size, filter);
+
+            assert_eq!(scaled.dimensions(), (25, 37));
+            for pixel in scaled.pixels().filter(|p| p.0[3] > 0) {
+                assert!(pixel.0[..3].iter().all(|&c| c >= 254), "{filter}: {pixel:?}");
+            }
+            assert!(scaled.pixels().any(|p| p.0[3] > 0 && p.0[3] < 255), "{filter}");
+        }
+    }
+
+    #[test]
+    fn exact_multiples_use_integer_scaling() {
+        let character = RgbaImage::from_fn(12, 18, |x, y| Rgba([x as u8 * 20, y as u8 * 10, 0, 255]));
+        for filter in ScalingFilter::ALL {
+            let scaled = scale_character(&character, &CharacterSize::Large, filter);
+            assert_eq!(scaled.dimensions(), (36, 54));
+            for (x, y, pixel) in scaled.enumerate_pixels() {
+                assert_eq!(pixel, character.get_pixel(x / 3, y / 3), "{filter}");
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_keeps_hard_edges() {
+        let scaled = scale_character(&half_white_character(), &CharacterSize::Race, ScalingFilter::Nearest);
+        assert!(scaled.pixels().all(|p| p.0[3] == 0 || p.0[3] == 255));
+    }
+}
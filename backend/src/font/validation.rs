@@ -0,0 +1,156 @@
+use std::collections::BTreeSet;
+
+use image::RgbaImage;
+
+use super::{font_file::FontFile, scaling::ScalingFilter};
+use crate::osd::{self, OsdOptions};
+
+/// Glyphs that are blank on purpose, these are not reported as transparent or empty.
+const BLANK_GLYPHS: [u16; 1] = [b' ' as u16];
+
+/// Problems with a font that cause the OSD to be rendered incorrectly. Glyphs are only checked when they are used by
+/// the OSD file and not masked.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FontValidationReport {
+    pub missing_alpha: bool,
+    /// Glyphs of which all pixels are transparent, except the glyphs that are blank on purpose like the space.
+    pub transparent_glyphs: Vec<u16>,
+    /// Opaque glyphs of which all pixels have the same color.
+    pub empty_glyphs: Vec<u16>,
+    /// Glyphs that are not in the font.
+    pub out_of_range_glyphs: Vec<u16>,
+}
+
+impl FontValidationReport {
+    pub fn new(font_file: &FontFile, osd_frames: &[osd::Frame], osd_options: &OsdOptions) -> Self {
+        let used_glyphs = osd_frames
+            .iter()
+            .flat_map(|frame| &frame.glyphs)
+            .filter(|glyph| {
+                glyph.index != 0 && !BLANK_GLYPHS.contains(&glyph.index) && !osd_options.get_mask(&glyph.grid_position)
+            })
+            .map(|glyph| glyph.index)
+            .collect::<BTreeSet<_>>();
+
+        let characters = font_file.characters(&font_file.character_size, ScalingFilter::default());
+        let mut report = Self {
+            missing_alpha: !font_file.has_alpha,
+            ..Default::default()
+        };
+
+        for glyph_index in used_glyphs {
+            match font_file
+                .character_index(glyph_index, osd_options.color_page)
+                .and_then(|index| characters.get(index))
+            {
+                None => report.out_of_range_glyphs.push(glyph_index),
+                Some(character) if is_transparent(character) => report.transparent_glyphs.push(glyph_index),
+                Some(character) if is_empty(character) => report.empty_glyphs.push(glyph_index),
+                Some(_) => {}
+            }
+        }
+
+        report
+    }
+
+    pub fn is_ok(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn is_transparent(character: &RgbaImage) -> bool {
+    character.pixels().all(|pixel| pixel.0[3] == 0)
+}
+
+fn is_empty(character: &RgbaImage) -> bool {
+    let first = character.get_pixel(0, 0);
+    character.pixels().all(|pixel| pixel == first)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgb, RgbImage};
+
+    use super::*;
+    use crate::{
+        font::CharacterSize,
+        osd::{Glyph, GridPosition},
+    };
+
+    fn frame(glyph_indices: &[u16]) -> osd::Frame {
+        osd::Frame {
+            time_millis: 0,
+            glyphs: glyph_indices
+                .iter()
+                .enumerate()
+                .map(|(x, &index)| Glyph {
+                    index,
+                    grid_position: GridPosition::new(x as u32, 0),
+                })
+                .collect(),
+        }
+    }
+
+    /// Font without alpha channel where character 1 is blank and all other characters have a white dot.
+    fn font_without_alpha() -> FontFile {
+        let size = CharacterSize::Race;
+        let image = RgbImage::from_fn(size.width(), size.height() * 256, |x, y| {
+            let character = y / size.height();
+            if character != 1 && x == 0 && y % size.height() == 0 {
+                Rgb([255, 255, 255])
+            } else {
+                Rgb([0, 0, 0])
+            }
+        });
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageOutputFormat::Png)
+            .unwrap();
+        FontFile::from_bytes("font.png".into(), &bytes).unwrap()
+    }
+
+    #[test]
+    fn report_font_issues() {
+        let font_file = font_without_alpha();
+        let frames = [frame(&[65, 1]), frame(&[1, 66, 300])];
+        let report = FontValidationReport::new(&font_file, &frames, &OsdOptions::default());
+
+        assert_eq!(
+            report,
+            FontValidationReport {
+                missing_alpha: true,
+                transparent_glyphs: vec![],
+                empty_glyphs: vec![1],
+                out_of_range_glyphs: vec![300],
+            }
+        );
+        assert!(!report.is_ok());
+    }
+
+    #[test]
+    fn report_transparent_glyphs_and_skip_masked_positions() {
+        let font_file = FontFile::bundled_text();
+        let mut osd_options = OsdOptions::default();
+        osd_options.toggle_mask(GridPosition::new(1, 0));
+
+        // Character 1 is transparent in the bundled font
+        let report = FontValidationReport::new(&font_file, &[frame(&[65, 300])], &osd_options);
+        assert!(report.is_ok());
+
+        let report = FontValidationReport::new(&font_file, &[frame(&[1])], &osd_options);
+        assert_eq!(report.transparent_glyphs, vec![1]);
+    }
+
+    #[test]
+    fn space_is_not_reported() {
+        let report = FontValidationReport::new(
+            &FontFile::bundled_text(),
+            &[frame(&[72, 32, 73])],
+            &OsdOptions::default(),
+        );
+        assert!(report.is_ok());
+
+        let report = FontValidationReport::new(&font_without_alpha(), &[frame(&[32])], &OsdOptions::default());
+        assert!(report.empty_glyphs.is_empty());
+    }
+}
@@ -1,8 +1,10 @@
 use std::fmt::Display;
 
+use serde::{Deserialize, Serialize};
+
 use super::error::OsdFileError;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FcFirmware {
     Betaflight,
     Inav,
@@ -12,6 +14,16 @@ pub enum FcFirmware {
     Unknown,
 }
 
+impl FcFirmware {
+    pub const KNOWN: [FcFirmware; 5] = [
+        FcFirmware::Betaflight,
+        FcFirmware::Inav,
+        FcFirmware::ArduPilot,
+        FcFirmware::Kiss,
+        FcFirmware::KissUltra,
+    ];
+}
+
 impl TryFrom<&str> for FcFirmware {
     type Error = OsdFileError;
 
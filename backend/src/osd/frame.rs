@@ -7,8 +7,8 @@ use super::{
 
 const TIMESTAMP_BYTES: usize = 4;
 const BYTES_PER_GLYPH: usize = 2;
-const GRID_WIDTH: usize = 53;
-const _GRID_HEIGHT: usize = 20;
+pub const GRID_WIDTH: usize = 53;
+pub const GRID_HEIGHT: usize = 20;
 
 #[derive(Debug, Clone)]
 pub struct Frame {
@@ -5,6 +5,8 @@ mod glyph;
 mod options;
 mod osd_file;
 
-pub use frame::Frame;
-pub use options::OsdOptions;
+pub use fc_firmware::FcFirmware;
+pub use frame::{Frame, GRID_HEIGHT, GRID_WIDTH};
+pub use glyph::{Glyph, GridPosition};
+pub use options::{OsdOptions, OsdScalingMode};
 pub use osd_file::OsdFile;
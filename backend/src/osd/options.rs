@@ -1,4 +1,8 @@
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    path::PathBuf,
+};
 
 use derivative::Derivative;
 use serde::{Deserialize, Serialize};
@@ -15,6 +19,30 @@ pub struct OsdOptions {
     #[serde(skip)]
     pub osd_playback_speed_factor: f32,
     pub masked_grid_positions: HashSet<Coordinates<u32>>,
+    pub scaling_mode: OsdScalingMode,
+    #[derivative(Default(value = "1.0"))]
+    pub scale: f32,
+    /// Column of a four color font that is used for the OSD.
+    pub color_page: u32,
+    /// Images that replace characters of the font, by index of the character in the font.
+    pub glyph_overrides: BTreeMap<u32, PathBuf>,
+    pub show_outline: bool,
+    /// Outline width in pixels at 1080p.
+    #[derivative(Default(value = "2.0"))]
+    pub outline_width: f32,
+    pub outline_color: [f32; 3],
+    pub show_shadow: bool,
+    /// Shadow offset in pixels at 1080p.
+    #[derivative(Default(value = "Coordinates::new(3.0, 3.0)"))]
+    pub shadow_offset: Coordinates<f32>,
+    #[derivative(Default(value = "2.0"))]
+    pub shadow_blur: f32,
+    pub shadow_color: [f32; 3],
+    #[derivative(Default(value = "0.6"))]
+    pub shadow_opacity: f32,
+    /// Opacity of the whole OSD, including the outline and shadow.
+    #[derivative(Default(value = "1.0"))]
+    pub opacity: f32,
 }
 
 impl OsdOptions {
@@ -34,3 +62,29 @@ impl OsdOptions {
         self.masked_grid_positions.clear();
     }
 }
+
+/// How the OSD grid is scaled to the video frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OsdScalingMode {
+    /// Keep the character aspect ratio and fit the whole grid inside the frame.
+    #[default]
+    Fit,
+    /// Keep the character aspect ratio and fill the frame, cropping the grid on one axis.
+    Fill,
+    /// Stretch the grid to the frame.
+    Stretch,
+}
+
+impl OsdScalingMode {
+    pub const ALL: [OsdScalingMode; 3] = [OsdScalingMode::Fit, OsdScalingMode::Fill, OsdScalingMode::Stretch];
+}
+
+impl Display for OsdScalingMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OsdScalingMode::Fit => write!(f, "Fit"),
+            OsdScalingMode::Fill => write!(f, "Fill"),
+            OsdScalingMode::Stretch => write!(f, "Stretch"),
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use std::{fmt::Display, sync::OnceLock};
+
+use image::{Pixel, Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+/// How the pixels of the overlay are combined with the pixels of the video.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlendMode {
+    /// Straight alpha in sRGB space, like most image editors. Anti-aliased edges of light glyphs on a dark background
+    /// look darker than the glyph.
+    #[default]
+    Srgb,
+    /// Premultiplied alpha in linear light, which keeps the brightness of anti-aliased edges like the goggles do.
+    Linear,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 2] = [BlendMode::Srgb, BlendMode::Linear];
+}
+
+impl Display for BlendMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlendMode::Srgb => write!(f, "sRGB"),
+            BlendMode::Linear => write!(f, "Linear light"),
+        }
+    }
+}
+
+/// Linear light value of every 8-bit sRGB value.
+fn srgb_to_linear_table() -> &'static [f32; 256] {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        std::array::from_fn(|value| {
+            let c = value as f32 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        })
+    })
+}
+
+fn linear_to_srgb(c: f32) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    let srgb = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (srgb * 255.0).round() as u8
+}
+
+/// Composite `src` over `dst`.
+#[inline]
+pub(crate) fn blend_pixel(dst: &mut Rgba<u8>, src: &Rgba<u8>, blend_mode: BlendMode) {
+    match blend_mode {
+        BlendMode::Srgb => dst.blend(src),
+        BlendMode::Linear => blend_linear(dst, src),
+    }
+}
+
+fn blend_linear(dst: &mut Rgba<u8>, src: &Rgba<u8>) {
+    let src_alpha = src.0[3];
+    if src_alpha == 0 {
+        return;
+    }
+    if src_alpha == 255 || dst.0[3] == 0 {
+        *dst = *src;
+        return;
+    }
+
+    // Premultiply both colors in linear light, composite the source over the destination and convert the result back
+    // to straight alpha in sRGB
+    let to_linear = srgb_to_linear_table();
+    let src_alpha = src_alpha as f32 / 255.0;
+    let dst_alpha = dst.0[3] as f32 / 255.0 * (1.0 - src_alpha);
+    let out_alpha = src_alpha + dst_alpha;
+    let channel = |i: usize| {
+        let premultiplied = to_linear[src.0[i] as usize] * src_alpha + to_linear[dst.0[i] as usize] * dst_alpha;
+        linear_to_srgb(premultiplied / out_alpha)
+    };
+    *dst = Rgba([channel(0), channel(1), channel(2), (out_alpha * 255.0).round() as u8]);
+}
+
+/// Composite `top` onto the image with its top left corner at `x`, `y`. Pixels outside of the image are skipped.
+pub(crate) fn blend_image(image: &mut RgbaImage, top: &RgbaImage, x: i32, y: i32, blend_mode: BlendMode) {
+    let (image_width, image_height) = image.dimensions();
+    for (top_x, top_y, pixel) in top.enumerate_pixels() {
+        if pixel.0[3] == 0 {
+            continue;
+        }
+        let image_x = x + top_x as i32;
+        let image_y = y + top_y as i32;
+        if image_x < 0 || image_y < 0 || image_x >= image_width as i32 || image_y >= image_height as i32 {
+            continue;
+        }
+        blend_pixel(image.get_pixel_mut(image_x as u32, image_y as u32), pixel, blend_mode);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        font::{FontFile, ScalingFilter},
+        osd::{self, OsdOptions},
+        overlay::{overlay_osd, overlay_srt_data},
+        srt::{SrtFont, SrtFrameData, SrtOptions},
+        util::Coordinates,
+    };
+
+    #[test]
+    fn half_covered_white_on_black() {
+        let black = Rgba([0, 0, 0, 255]);
+        let half_white = Rgba([255, 255, 255, 128]);
+
+        let mut srgb = black;
+        blend_pixel(&mut srgb, &half_white, BlendMode::Srgb);
+        assert_eq!(srgb.0[..3], [128, 128, 128]);
+
+        // Half the light of white is a lot brighter than the middle of the sRGB range
+        let mut linear = black;
+        blend_pixel(&mut linear, &half_white, BlendMode::Linear);
+        assert_eq!(linear, Rgba([188, 188, 188, 255]));
+    }
+
+    #[test]
+    fn linear_blend_keeps_opaque_and_transparent_pixels() {
+        let mut pixel = Rgba([10, 20, 30, 255]);
+        blend_pixel(&mut pixel, &Rgba([200, 100, 0, 0]), BlendMode::Linear);
+        assert_eq!(pixel, Rgba([10, 20, 30, 255]));
+        blend_pixel(&mut pixel, &Rgba([200, 100, 0, 255]), BlendMode::Linear);
+        assert_eq!(pixel, Rgba([200, 100, 0, 255]));
+
+        // On a transparent background the overlay pixel is kept as is
+        let mut transparent = Rgba([0, 0, 0, 0]);
+        blend_pixel(&mut transparent, &Rgba([255, 255, 255, 77]), BlendMode::Linear);
+        assert_eq!(transparent, Rgba([255, 255, 255, 77]));
+    }
+
+    /// Dark gradient, like a video frame.
+    fn golden_background() -> RgbaImage {
+        RgbaImage::from_fn(480, 270, |x, y| Rgba([(x / 8) as u8, (y / 8) as u8 + 20, 60, 255]))
+    }
+
+    /// Render the OSD and SRT data on the golden background.
+    fn render_golden_scene(blend_mode: BlendMode) -> RgbaImage {
+        let mut image = golden_background();
+
+        let glyphs = "ALT 123M"
+            .bytes()
+            .enumerate()
+            .map(|(i, c)| osd::Glyph {
+                index: c as u16,
+                grid_position: osd::GridPosition { x: 2 + i as u32, y: 2 },
+            })
+            .collect();
+        let osd_frame = osd::Frame { time_millis: 0, glyphs };
+        // Undo the centering of the grid so the OSD is at the top left like in the golden images
+        let osd_options = OsdOptions {
+            position: Coordinates::new(-1, 5),
+            ..Default::default()
+        };
+        overlay_osd(
+            &mut image,
+            &osd_frame,
+            &FontFile::bundled_text(),
+            &osd_options,
+            ScalingFilter::default(),
+            blend_mode,
+        );
+
+        let srt_data = "Signal:4 CH:1 FlightTime:93 SBat:16.4V GBat:12.1V Delay:28ms Bitrate:25.0Mbps Distance:120m"
+            .parse::<SrtFrameData>()
+            .unwrap();
+        let srt_options = SrtOptions {
+            show_outline: true,
+            ..Default::default()
+        };
+        overlay_srt_data(
+            &mut image,
+            &srt_data,
+            &SrtFont::bundled(),
+            &srt_options,
+            0.0,
+            blend_mode,
+        );
+
+        image
+    }
+
+    /// Compare with the golden image in `resources/golden`. Run the tests with `UPDATE_GOLDEN_IMAGES=1` to write the
+    /// golden images after an intended change.
+    fn assert_matches_golden(image: &RgbaImage, name: &str) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!("../resources/golden/{name}.png"));
+        if std::env::var_os("UPDATE_GOLDEN_IMAGES").is_some() {
+            image.save(&path).unwrap();
+            return;
+        }
+
+        let golden = image::open(&path).unwrap().into_rgba8();
+        assert_eq!(image.dimensions(), golden.dimensions());
+        // Allow rounding differences of float math between platforms
+        let different_pixels = image
+            .pixels()
+            .zip(golden.pixels())
+            .filter(|(a, b)| a.0.iter().zip(b.0).any(|(a, b)| a.abs_diff(b) > 1))
+            .count();
+        assert_eq!(different_pixels, 0, "{different_pixels} pixels differ from {name}.png");
+    }
+
+    #[test]
+    fn golden_images() {
+        let srgb = render_golden_scene(BlendMode::Srgb);
+        let linear = render_golden_scene(BlendMode::Linear);
+        assert_matches_golden(&srgb, "blend_srgb");
+        assert_matches_golden(&linear, "blend_linear");
+
+        // The modes only differ at the anti-aliased edges, which are never darker in linear light
+        let brightness = |pixel: &Rgba<u8>| pixel.0[..3].iter().map(|&c| c as u32).sum::<u32>();
+        let mut edge_pixels = 0;
+        for (srgb, linear) in srgb.pixels().zip(linear.pixels()) {
+            if srgb != linear {
+                edge_pixels += 1;
+                assert!(
+                    brightness(linear) + 3 >= brightness(srgb),
+                    "{linear:?} is darker than {srgb:?}"
+                );
+            }
+        }
+        assert!(edge_pixels > 0);
+    }
+}
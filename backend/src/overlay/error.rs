@@ -0,0 +1,31 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::srt::SrtFontError;
+
+#[derive(Error, Debug)]
+pub enum OverlayLayerError {
+    #[error("Unable to open image {path:?}, source: {source}")]
+    UnableToOpenImage { path: PathBuf, source: image::ImageError },
+
+    #[error("Unable to open text layer font, source: {source}")]
+    UnableToOpenFont {
+        #[from]
+        source: SrtFontError,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum FrameCompositeError {
+    #[error("Decoded video frame has {actual} bytes instead of {expected} for {width}x{height} pixels")]
+    InvalidFrameSize {
+        width: u32,
+        height: u32,
+        expected: usize,
+        actual: usize,
+    },
+
+    #[error("Drawing the overlay on a video frame failed: {message}")]
+    Panicked { message: String },
+}
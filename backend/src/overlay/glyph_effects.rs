@@ -0,0 +1,261 @@
+use std::sync::{Arc, OnceLock};
+
+use image::{GrayImage, Luma, Rgba, RgbaImage};
+use imageproc::filter::gaussian_blur_f32;
+
+use super::{
+    blend::BlendMode,
+    mask::{dilate_mask, draw_mask},
+};
+use crate::osd::OsdOptions;
+
+/// Outline, drop shadow and opacity applied to the OSD characters, with sizes in pixels of the output frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphEffects {
+    outline_width: u32,
+    outline_color: Rgba<u8>,
+    shadow: Option<GlyphShadow>,
+    opacity: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct GlyphShadow {
+    offset_x: i32,
+    offset_y: i32,
+    blur: f32,
+    color: Rgba<u8>,
+}
+
+impl GlyphEffects {
+    /// Effects from the OSD options for a frame of the given height. The sizes in the options are specified for 1080p.
+    pub fn new(osd_options: &OsdOptions, frame_height: u32) -> Self {
+        let scale_factor = frame_height as f32 / 1080.0;
+        let outline_width = if osd_options.show_outline {
+            (osd_options.outline_width * scale_factor).round().max(0.0) as u32
+        } else {
+            0
+        };
+        let shadow = osd_options.show_shadow.then(|| GlyphShadow {
+            offset_x: (osd_options.shadow_offset.x * scale_factor).round() as i32,
+            offset_y: (osd_options.shadow_offset.y * scale_factor).round() as i32,
+            blur: (osd_options.shadow_blur * scale_factor).max(0.0),
+            color: to_rgba(osd_options.shadow_color, osd_options.shadow_opacity),
+        });
+
+        Self {
+            outline_width,
+            outline_color: to_rgba(osd_options.outline_color, 1.0),
+            shadow,
+            opacity: osd_options.opacity.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.outline_width == 0 && self.shadow.is_none() && self.opacity >= 1.0
+    }
+
+    /// Number of pixels the effects layer extends beyond the character on every side.
+    pub fn margin(&self) -> u32 {
+        let shadow_extent = self.shadow.as_ref().map_or(0, |shadow| {
+            shadow.offset_x.unsigned_abs().max(shadow.offset_y.unsigned_abs()) + (3.0 * shadow.blur).ceil() as u32
+        });
+        self.outline_width + shadow_extent
+    }
+
+    /// Draw the shadow and outline of a character on a new image that is larger than the character by
+    /// [`margin`](Self::margin) on every side, or `None` when neither is enabled. The outline is drawn over the shadow
+    /// with straight alpha, the blend mode only applies when the layer is drawn on the frame.
+    pub fn effects_layer(&self, character: &RgbaImage) -> Option<RgbaImage> {
+        if self.outline_width == 0 && self.shadow.is_none() {
+            return None;
+        }
+
+        let margin = self.margin();
+        let (width, height) = character.dimensions();
+        let alpha_mask = GrayImage::from_fn(width + 2 * margin, height + 2 * margin, |x, y| {
+            if x < margin || y < margin || x >= width + margin || y >= height + margin {
+                Luma([0])
+            } else {
+                Luma([character.get_pixel(x - margin, y - margin).0[3]])
+            }
+        });
+        let outline_mask = (self.outline_width > 0).then(|| dilate_mask(&alpha_mask, self.outline_width));
+
+        let mut styled = RgbaImage::new(alpha_mask.width(), alpha_mask.height());
+        if let Some(shadow) = &self.shadow {
+            let shadow_source = outline_mask.as_ref().unwrap_or(&alpha_mask);
+            let shadow_mask = if shadow.blur > 0.0 {
+                gaussian_blur_f32(shadow_source, shadow.blur)
+            } else {
+                shadow_source.clone()
+            };
+            draw_mask(
+                &mut styled,
+                &shadow_mask,
+                shadow.offset_x,
+                shadow.offset_y,
+                shadow.color,
+                BlendMode::Srgb,
+            );
+        }
+        if let Some(outline_mask) = &outline_mask {
+            draw_mask(&mut styled, outline_mask, 0, 0, self.outline_color, BlendMode::Srgb);
+        }
+        Some(self.faded(styled))
+    }
+
+    /// The character with the opacity applied, or `None` when it's fully opaque.
+    pub fn faded_character(&self, character: &RgbaImage) -> Option<RgbaImage> {
+        (self.opacity < 1.0).then(|| self.faded(character.clone()))
+    }
+
+    fn faded(&self, mut image: RgbaImage) -> RgbaImage {
+        if self.opacity < 1.0 {
+            for pixel in image.pixels_mut() {
+                pixel.0[3] = (pixel.0[3] as f32 * self.opacity).round() as u8;
+            }
+        }
+        image
+    }
+}
+
+fn to_rgba(color: [f32; 3], opacity: f32) -> Rgba<u8> {
+    let [r, g, b] = color.map(|c| (c * 255.0) as u8);
+    Rgba([r, g, b, (opacity.clamp(0.0, 1.0) * 255.0) as u8])
+}
+
+/// A character and its effects layer. All effects layers of a frame are drawn before the characters so the outline of
+/// a character doesn't cover its neighbors.
+pub struct StyledCharacter<'a> {
+    /// Shadow and outline, larger than the character by [`GlyphEffects::margin`] on every side.
+    pub effects_layer: Option<&'a RgbaImage>,
+    pub character: &'a RgbaImage,
+}
+
+/// Font characters with glyph effects applied. Characters are styled the first time they are used, most fonts contain
+/// many characters that never appear in the OSD.
+pub struct StyledCharacters {
+    characters: Arc<Vec<RgbaImage>>,
+    effects: GlyphEffects,
+    styled: Vec<OnceLock<(Option<RgbaImage>, Option<RgbaImage>)>>,
+}
+
+impl StyledCharacters {
+    pub fn new(characters: Arc<Vec<RgbaImage>>, effects: GlyphEffects) -> Self {
+        let styled = characters.iter().map(|_| OnceLock::new()).collect();
+        Self {
+            characters,
+            effects,
+            styled,
+        }
+    }
+
+    pub fn effects(&self) -> &GlyphEffects {
+        &self.effects
+    }
+
+    pub fn get(&self, index: usize) -> Option<StyledCharacter<'_>> {
+        let character = self.characters.get(index)?;
+        if self.effects.is_none() {
+            return Some(StyledCharacter {
+                effects_layer: None,
+                character,
+            });
+        }
+
+        let (effects_layer, faded_character) = self.styled[index].get_or_init(|| {
+            (
+                self.effects.effects_layer(character),
+                self.effects.faded_character(character),
+            )
+        });
+        Some(StyledCharacter {
+            effects_layer: effects_layer.as_ref(),
+            character: faded_character.as_ref().unwrap_or(character),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Coordinates;
+
+    /// White 4x4 square in the middle of a transparent 12x12 character.
+    fn square_character() -> RgbaImage {
+        RgbaImage::from_fn(12, 12, |x, y| {
+            if (4..8).contains(&x) && (4..8).contains(&y) {
+                Rgba([255, 255, 255, 255])
+            } else {
+                Rgba([0, 0, 0, 0])
+            }
+        })
+    }
+
+    #[test]
+    fn no_effects_by_default() {
+        let effects = GlyphEffects::new(&OsdOptions::default(), 1080);
+        assert!(effects.is_none());
+        assert_eq!(effects.margin(), 0);
+
+        let characters = Arc::new(vec![square_character()]);
+        let styled = StyledCharacters::new(characters.clone(), effects);
+        let character = styled.get(0).unwrap();
+        assert!(character.effects_layer.is_none());
+        assert!(std::ptr::eq(character.character, &characters[0]));
+        assert!(styled.get(1).is_none());
+    }
+
+    #[test]
+    fn outline_surrounds_character() {
+        let osd_options = OsdOptions {
+            show_outline: true,
+            outline_width: 2.0,
+            outline_color: [1.0, 0.0, 0.0],
+            ..Default::default()
+        };
+        let effects = GlyphEffects::new(&osd_options, 1080);
+        assert_eq!(effects.margin(), 2);
+
+        let layer = effects.effects_layer(&square_character()).unwrap();
+        assert_eq!(layer.dimensions(), (16, 16));
+        // The outline covers the character and the pixels next to it, the corners stay transparent
+        assert_eq!(*layer.get_pixel(7, 7), Rgba([255, 0, 0, 255]));
+        assert_eq!(*layer.get_pixel(5, 7), Rgba([255, 0, 0, 255]));
+        assert_eq!(layer.get_pixel(0, 0).0[3], 0);
+        assert_eq!(effects.faded_character(&square_character()), None);
+    }
+
+    #[test]
+    fn shadow_is_offset_and_scaled_with_frame_height() {
+        let osd_options = OsdOptions {
+            show_shadow: true,
+            shadow_offset: Coordinates::new(2.0, 0.0),
+            shadow_blur: 0.0,
+            shadow_opacity: 1.0,
+            ..Default::default()
+        };
+        // Sizes are doubled at 2160p
+        let effects = GlyphEffects::new(&osd_options, 2160);
+        assert_eq!(effects.margin(), 4);
+
+        let layer = effects.effects_layer(&square_character()).unwrap();
+        assert_eq!(*layer.get_pixel(14, 10), Rgba([0, 0, 0, 255]));
+        assert_eq!(layer.get_pixel(7, 10).0[3], 0);
+    }
+
+    #[test]
+    fn opacity_applies_to_whole_glyph() {
+        let osd_options = OsdOptions {
+            opacity: 0.5,
+            ..Default::default()
+        };
+        let effects = GlyphEffects::new(&osd_options, 1080);
+        assert!(!effects.is_none());
+
+        assert_eq!(effects.effects_layer(&square_character()), None);
+        let faded = effects.faded_character(&square_character()).unwrap();
+        assert_eq!(*faded.get_pixel(5, 5), Rgba([255, 255, 255, 128]));
+        assert_eq!(faded.get_pixel(0, 0).0[3], 0);
+    }
+}
@@ -1,5 +1,3 @@
-use std::{iter::Peekable, vec::IntoIter};
-
 use crossbeam_channel::{Receiver, Sender};
 use ffmpeg_sidecar::{
     child::FfmpegChild,
@@ -8,70 +6,114 @@ use ffmpeg_sidecar::{
 };
 use image::{Rgba, RgbaImage};
 
-use super::{overlay_osd, overlay_srt_data};
-use crate::{
-    ffmpeg::{handle_decoder_events, FromFfmpegMessage, ToFfmpegMessage},
-    font,
-    osd::{self, OsdOptions},
-    srt::{self, SrtOptions},
+use super::{
+    error::FrameCompositeError,
+    layer::OverlayLayer,
+    parallel::{default_capacity, OrderedParallelMap, Panicked},
+    yuv::YuvCompositor,
 };
+use crate::ffmpeg::{handle_decoder_events, FromFfmpegMessage, ToFfmpegMessage};
 
-pub struct FrameOverlayIter<'a> {
-    decoder_iter: FfmpegIterator,
-    decoder_process: FfmpegChild,
-    osd_frames_iter: Peekable<IntoIter<osd::Frame>>,
-    srt_frames_iter: Peekable<IntoIter<srt::SrtFrame>>,
-    font_file: font::FontFile,
-    osd_options: OsdOptions,
-    srt_options: SrtOptions,
-    srt_font: rusttype::Font<'a>,
-    current_osd_frame: osd::Frame,
-    current_srt_frame: srt::SrtFrame,
+/// Pixel format of the raw video frames that are decoded, composited and encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramePixelFormat {
+    Rgba,
+    /// Planar YUV 4:2:0. The overlay is converted to YUV and blended into the planes of the decoded frames, which
+    /// avoids converting every frame to RGBA and back.
+    Yuv420p,
+}
+
+impl FramePixelFormat {
+    /// Name of the pixel format in ffmpeg.
+    pub fn pix_fmt(&self) -> &'static str {
+        match self {
+            FramePixelFormat::Rgba => "rgba",
+            FramePixelFormat::Yuv420p => "yuv420p",
+        }
+    }
+}
+
+/// Iterator over the decoded video frames with the overlay layers drawn on top. Frames are decoded in order, the
+/// layers are drawn on multiple frames in parallel. The iterator ends when a frame can't be composited, after the error
+/// is sent as [`FromFfmpegMessage::DecoderFatalError`].
+pub struct FrameOverlayIter {
+    frames: OrderedParallelMap<DecodedFrameIter, Result<OutputVideoFrame, FrameCompositeError>>,
     ffmpeg_sender: Sender<FromFfmpegMessage>,
-    ffmpeg_receiver: Receiver<ToFfmpegMessage>,
-    chroma_key: Option<Rgba<u8>>,
+    failed: bool,
 }
 
-impl<'a> FrameOverlayIter<'a> {
-    #[tracing::instrument(skip(decoder_iter, decoder_process, osd_frames, font_file), level = "debug")]
+impl FrameOverlayIter {
+    #[tracing::instrument(skip(decoder_iter, decoder_process, layers), level = "debug")]
     pub fn new(
         decoder_iter: FfmpegIterator,
         decoder_process: FfmpegChild,
-        osd_frames: Vec<osd::Frame>,
-        srt_frames: Vec<srt::SrtFrame>,
-        font_file: font::FontFile,
-        srt_font: rusttype::Font<'a>,
-        osd_options: &OsdOptions,
-        srt_options: &SrtOptions,
+        layers: Vec<Box<dyn OverlayLayer>>,
         ffmpeg_sender: Sender<FromFfmpegMessage>,
         ffmpeg_receiver: Receiver<ToFfmpegMessage>,
-        chroma_key: Option<[f32; 3]>,
+        background: Option<Rgba<u8>>,
+        pixel_format: FramePixelFormat,
     ) -> Self {
-        let mut osd_frames_iter = osd_frames.into_iter();
-        let mut srt_frames_iter = srt_frames.into_iter();
-        let first_osd_frame = osd_frames_iter.next().unwrap();
-        let first_srt_frame = srt_frames_iter.next().unwrap();
-        let chroma_key =
-            chroma_key.map(|c| Rgba([(c[0] * 255.0) as u8, (c[1] * 255.0) as u8, (c[2] * 255.0) as u8, 255]));
-        Self {
+        let decoded_frames = DecodedFrameIter {
             decoder_iter,
             decoder_process,
-            osd_frames_iter: osd_frames_iter.peekable(),
-            srt_frames_iter: srt_frames_iter.peekable(),
-            font_file,
-            osd_options: osd_options.clone(),
-            srt_options: srt_options.clone(),
-            srt_font: srt_font.clone(),
-            current_osd_frame: first_osd_frame,
-            current_srt_frame: first_srt_frame,
-            ffmpeg_sender,
+            ffmpeg_sender: ffmpeg_sender.clone(),
             ffmpeg_receiver,
-            chroma_key,
+        };
+        let compositor = FrameCompositor { layers, background };
+
+        let frames = match pixel_format {
+            FramePixelFormat::Rgba => OrderedParallelMap::new(decoded_frames, default_capacity(), move |video_frame| {
+                compositor.composite(video_frame)
+            }),
+            FramePixelFormat::Yuv420p => {
+                let compositor = YuvCompositor::new(compositor);
+                OrderedParallelMap::new(decoded_frames, default_capacity(), move |video_frame| {
+                    compositor.composite(video_frame)
+                })
+            }
+        };
+        Self {
+            frames,
+            ffmpeg_sender,
+            failed: false,
+        }
+    }
+}
+
+impl Iterator for FrameOverlayIter {
+    type Item = OutputVideoFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let result = self
+            .frames
+            .next()?
+            .unwrap_or_else(|Panicked(message)| Err(FrameCompositeError::Panicked { message }));
+        match result {
+            Ok(video_frame) => Some(video_frame),
+            Err(error) => {
+                tracing::error!("Failed to composite video frame: {}", error);
+                self.ffmpeg_sender
+                    .send(FromFfmpegMessage::DecoderFatalError(error.to_string()))
+                    .ok();
+                self.failed = true;
+                None
+            }
         }
     }
 }
 
-impl Iterator for FrameOverlayIter<'_> {
+struct DecodedFrameIter {
+    decoder_iter: FfmpegIterator,
+    decoder_process: FfmpegChild,
+    ffmpeg_sender: Sender<FromFfmpegMessage>,
+    ffmpeg_receiver: Receiver<ToFfmpegMessage>,
+}
+
+impl Iterator for DecodedFrameIter {
     type Item = OutputVideoFrame;
 
     fn next(&mut self) -> Option<Self::Item> {
@@ -81,44 +123,7 @@ impl Iterator for FrameOverlayIter<'_> {
         }
 
         self.decoder_iter.find_map(|e| match e {
-            FfmpegEvent::OutputFrame(mut video_frame) => {
-                // For every video frame check if frame time is later than the next OSD frame time.
-                // If so advance the iterator over the OSD frames so we use the correct OSD frame
-                // for this video frame
-                if let Some(next_osd_frame) = self.osd_frames_iter.peek() {
-                    let next_osd_frame_secs = next_osd_frame.time_millis as f32 / 1000.0;
-                    if video_frame.timestamp > next_osd_frame_secs * self.osd_options.osd_playback_speed_factor {
-                        self.current_osd_frame = self.osd_frames_iter.next().unwrap();
-                    }
-                }
-
-                if let Some(next_srt_frame) = self.srt_frames_iter.peek() {
-                    let next_srt_start_time_secs = next_srt_frame.start_time_secs;
-                    if video_frame.timestamp > next_srt_start_time_secs {
-                        self.current_srt_frame = self.srt_frames_iter.next().unwrap();
-                    }
-                }
-
-                let mut frame_image = if let Some(chroma_key) = self.chroma_key {
-                    RgbaImage::from_pixel(video_frame.width, video_frame.height, chroma_key)
-                } else {
-                    RgbaImage::from_raw(video_frame.width, video_frame.height, video_frame.data).unwrap()
-                };
-
-                overlay_osd(
-                    &mut frame_image,
-                    &self.current_osd_frame,
-                    &self.font_file,
-                    &self.osd_options,
-                );
-
-                if let Some(srt_data) = &self.current_srt_frame.data {
-                    overlay_srt_data(&mut frame_image, srt_data, &self.srt_font, &self.srt_options);
-                }
-
-                video_frame.data = frame_image.as_raw().to_vec();
-                Some(video_frame)
-            }
+            FfmpegEvent::OutputFrame(video_frame) => Some(video_frame),
             other_event => {
                 handle_decoder_events(other_event, &self.ffmpeg_sender);
                 None
@@ -126,3 +131,47 @@ impl Iterator for FrameOverlayIter<'_> {
         })
     }
 }
+
+/// The layers and background of the rendered frames. Shared by the threads that composite the frames.
+pub(super) struct FrameCompositor {
+    pub(super) layers: Vec<Box<dyn OverlayLayer>>,
+    /// Color that replaces the video, for chroma keying or transparent export.
+    pub(super) background: Option<Rgba<u8>>,
+}
+
+impl FrameCompositor {
+    fn composite(&self, mut video_frame: OutputVideoFrame) -> Result<OutputVideoFrame, FrameCompositeError> {
+        let (width, height) = (video_frame.width, video_frame.height);
+        let mut frame_image = if let Some(background) = self.background {
+            RgbaImage::from_pixel(width, height, background)
+        } else {
+            let actual = video_frame.data.len();
+            RgbaImage::from_raw(width, height, video_frame.data).ok_or(FrameCompositeError::InvalidFrameSize {
+                width,
+                height,
+                expected: width as usize * height as usize * 4,
+                actual,
+            })?
+        };
+
+        self.draw(&mut frame_image, video_frame.timestamp);
+
+        video_frame.data = frame_image.into_raw();
+        Ok(video_frame)
+    }
+
+    /// Draw all layers on `image` in order.
+    pub(super) fn draw(&self, image: &mut RgbaImage, video_time_secs: f32) {
+        for layer in &self.layers {
+            layer.draw(image, video_time_secs);
+        }
+    }
+
+    /// Identifies what the layers draw at `video_time_secs`.
+    pub(super) fn content_ids(&self, video_time_secs: f32) -> Vec<u64> {
+        self.layers
+            .iter()
+            .map(|layer| layer.content_id(video_time_secs))
+            .collect()
+    }
+}
@@ -0,0 +1,264 @@
+use std::{fmt::Display, sync::Arc};
+
+use image::RgbaImage;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    blend::BlendMode,
+    overlay_osd, overlay_srt_data,
+    srt::blink_on,
+    timeline::{osd_frame_index, srt_frame_index},
+};
+use crate::{
+    font::{FontFile, ScalingFilter},
+    osd::{self, OsdOptions},
+    srt::{SrtFont, SrtFrame, SrtOptions},
+};
+
+/// Something that is drawn on top of the video, like the OSD or the SRT data. Layers are drawn in order on every
+/// frame, on multiple threads at the same time.
+pub trait OverlayLayer: Send + Sync {
+    /// Draw the layer as it should appear at `video_time_secs`.
+    fn draw(&self, image: &mut RgbaImage, video_time_secs: f32);
+
+    /// Identifies what the layer draws at `video_time_secs`. Frames only have to be redrawn when it changes.
+    fn content_id(&self, video_time_secs: f32) -> u64;
+}
+
+/// Layers that are kept between renders, like opened image and text layers, are shared with the render.
+impl<L: OverlayLayer + ?Sized> OverlayLayer for Arc<L> {
+    fn draw(&self, image: &mut RgbaImage, video_time_secs: f32) {
+        (**self).draw(image, video_time_secs)
+    }
+
+    fn content_id(&self, video_time_secs: f32) -> u64 {
+        (**self).content_id(video_time_secs)
+    }
+}
+
+/// The built-in layers, in the order they can be arranged in the render settings. All image and all text layers are
+/// drawn together in the order they are listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayerKind {
+    Osd,
+    Srt,
+    Images,
+    Texts,
+}
+
+impl LayerKind {
+    pub const DEFAULT_ORDER: [LayerKind; 4] = [LayerKind::Osd, LayerKind::Srt, LayerKind::Images, LayerKind::Texts];
+}
+
+impl Display for LayerKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LayerKind::Osd => write!(f, "OSD"),
+            LayerKind::Srt => write!(f, "SRT data"),
+            LayerKind::Images => write!(f, "Images"),
+            LayerKind::Texts => write!(f, "Text"),
+        }
+    }
+}
+
+pub struct OsdLayer {
+    frames: Vec<osd::Frame>,
+    font_file: FontFile,
+    osd_options: OsdOptions,
+    scaling_filter: ScalingFilter,
+    /// Size of the video the position offset in the OSD options is set for. The offset is scaled when the layer is
+    /// drawn on frames of another size, like upscaled video.
+    video_width: u32,
+    video_height: u32,
+    blend_mode: BlendMode,
+}
+
+impl OsdLayer {
+    pub fn new(
+        frames: Vec<osd::Frame>,
+        font_file: FontFile,
+        osd_options: &OsdOptions,
+        scaling_filter: ScalingFilter,
+        (video_width, video_height): (u32, u32),
+        blend_mode: BlendMode,
+    ) -> Self {
+        Self {
+            frames,
+            font_file,
+            osd_options: osd_options.clone(),
+            scaling_filter,
+            video_width,
+            video_height,
+            blend_mode,
+        }
+    }
+
+    fn frame_index(&self, video_time_secs: f32) -> Option<usize> {
+        osd_frame_index(
+            &self.frames,
+            self.osd_options.osd_playback_speed_factor,
+            video_time_secs,
+        )
+    }
+}
+
+impl OverlayLayer for OsdLayer {
+    fn draw(&self, image: &mut RgbaImage, video_time_secs: f32) {
+        let Some(frame) = self.frame_index(video_time_secs).map(|index| &self.frames[index]) else {
+            return;
+        };
+
+        if image.dimensions() == (self.video_width, self.video_height) {
+            overlay_osd(
+                image,
+                frame,
+                &self.font_file,
+                &self.osd_options,
+                self.scaling_filter,
+                self.blend_mode,
+            );
+        } else {
+            let scale_x = image.width() as f32 / self.video_width as f32;
+            let scale_y = image.height() as f32 / self.video_height as f32;
+            let mut osd_options = self.osd_options.clone();
+            osd_options.position.x = (osd_options.position.x as f32 * scale_x).round() as i32;
+            osd_options.position.y = (osd_options.position.y as f32 * scale_y).round() as i32;
+            overlay_osd(
+                image,
+                frame,
+                &self.font_file,
+                &osd_options,
+                self.scaling_filter,
+                self.blend_mode,
+            );
+        }
+    }
+
+    fn content_id(&self, video_time_secs: f32) -> u64 {
+        self.frame_index(video_time_secs).map_or(u64::MAX, |index| index as u64)
+    }
+}
+
+pub struct SrtLayer {
+    frames: Vec<SrtFrame>,
+    srt_font: SrtFont,
+    srt_options: SrtOptions,
+    blend_mode: BlendMode,
+}
+
+impl SrtLayer {
+    pub fn new(frames: Vec<SrtFrame>, srt_font: SrtFont, srt_options: &SrtOptions, blend_mode: BlendMode) -> Self {
+        Self {
+            frames,
+            srt_font,
+            srt_options: srt_options.clone(),
+            blend_mode,
+        }
+    }
+
+    fn frame_index(&self, video_time_secs: f32) -> Option<usize> {
+        srt_frame_index(&self.frames, video_time_secs)
+    }
+}
+
+impl OverlayLayer for SrtLayer {
+    fn draw(&self, image: &mut RgbaImage, video_time_secs: f32) {
+        if let Some(srt_data) = self
+            .frame_index(video_time_secs)
+            .and_then(|index| self.frames[index].data.as_ref())
+        {
+            overlay_srt_data(
+                image,
+                srt_data,
+                &self.srt_font,
+                &self.srt_options,
+                video_time_secs,
+                self.blend_mode,
+            );
+        }
+    }
+
+    fn content_id(&self, video_time_secs: f32) -> u64 {
+        let frame_id = self
+            .frame_index(video_time_secs)
+            .map_or(u64::MAX >> 1, |index| index as u64);
+        frame_id << 1 | blink_on(video_time_secs) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn osd_layer(frame_times_millis: &[u32]) -> OsdLayer {
+        let frames = frame_times_millis
+            .iter()
+            .map(|&time_millis| osd::Frame {
+                time_millis,
+                glyphs: Vec::new(),
+            })
+            .collect();
+        OsdLayer::new(
+            frames,
+            FontFile::bundled_text(),
+            &OsdOptions::default(),
+            ScalingFilter::default(),
+            (1920, 1080),
+            BlendMode::default(),
+        )
+    }
+
+    #[test]
+    fn osd_frame_for_video_time() {
+        let layer = osd_layer(&[0, 100, 200, 300]);
+        assert_eq!(layer.content_id(0.0), 0);
+        assert_eq!(layer.content_id(0.1), 0);
+        assert_eq!(layer.content_id(0.15), 1);
+        assert_eq!(layer.content_id(0.25), 2);
+        assert_eq!(layer.content_id(10.0), 3);
+    }
+
+    /// Position of the top left pixel that the layer draws on a frame of `width` by `height` pixels, when the OSD
+    /// offset is `position` in a video of `video_size`.
+    fn first_drawn_pixel(position: (i32, i32), video_size: (u32, u32), width: u32, height: u32) -> (u32, u32) {
+        let mut osd_options = OsdOptions::default();
+        (osd_options.position.x, osd_options.position.y) = position;
+        let frames = vec![osd::Frame {
+            time_millis: 0,
+            glyphs: vec![osd::Glyph {
+                index: b'A' as u16,
+                grid_position: osd::GridPosition { x: 0, y: 0 },
+            }],
+        }];
+        let layer = OsdLayer::new(
+            frames,
+            FontFile::bundled_text(),
+            &osd_options,
+            ScalingFilter::default(),
+            video_size,
+            BlendMode::default(),
+        );
+
+        let mut image = RgbaImage::new(width, height);
+        layer.draw(&mut image, 0.0);
+        let drawn = |x: u32, y: u32| image.get_pixel(x, y).0[3] > 0;
+        let first_column = (0..width).find(|&x| (0..height).any(|y| drawn(x, y))).unwrap();
+        let first_row = (0..height).find(|&y| (0..width).any(|x| drawn(x, y))).unwrap();
+        (first_column, first_row)
+    }
+
+    #[test]
+    fn osd_position_is_scaled_to_frame_size() {
+        // Drawn at twice the video size the offset is doubled
+        assert_eq!(
+            first_drawn_pixel((30, 20), (960, 540), 1920, 1080),
+            first_drawn_pixel((60, 40), (1920, 1080), 1920, 1080)
+        );
+
+        // A 4:3 video that is upscaled to 2560x1440 is stretched more horizontally than vertically
+        assert_eq!(
+            first_drawn_pixel((30, 30), (1440, 1080), 2560, 1440),
+            first_drawn_pixel((53, 40), (2560, 1440), 2560, 1440)
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use image::{GrayImage, Luma, Rgba, RgbaImage};
+
+use super::blend::{blend_pixel, BlendMode};
+
+/// Blends `color` onto the image using the mask values as coverage.
+pub(crate) fn draw_mask(
+    image: &mut RgbaImage,
+    mask: &GrayImage,
+    x: i32,
+    y: i32,
+    color: Rgba<u8>,
+    blend_mode: BlendMode,
+) {
+    let (image_width, image_height) = image.dimensions();
+    for (mask_x, mask_y, coverage) in mask.enumerate_pixels() {
+        if coverage.0[0] == 0 {
+            continue;
+        }
+        let image_x = x + mask_x as i32;
+        let image_y = y + mask_y as i32;
+        if image_x < 0 || image_y < 0 || image_x >= image_width as i32 || image_y >= image_height as i32 {
+            continue;
+        }
+        let mut pixel_color = color;
+        pixel_color.0[3] = (color.0[3] as u16 * coverage.0[0] as u16 / 255) as u8;
+        blend_pixel(
+            image.get_pixel_mut(image_x as u32, image_y as u32),
+            &pixel_color,
+            blend_mode,
+        );
+    }
+}
+
+/// Grayscale dilation with a round kernel so the anti-aliased edges of the text are preserved in the outline.
+pub(crate) fn dilate_mask(mask: &GrayImage, radius: u32) -> GrayImage {
+    let (width, height) = mask.dimensions();
+    let radius = radius as i32;
+    let offsets = (-radius..=radius)
+        .flat_map(|dy| (-radius..=radius).map(move |dx| (dx, dy)))
+        .filter(|(dx, dy)| dx * dx + dy * dy <= radius * radius)
+        .collect::<Vec<_>>();
+
+    GrayImage::from_fn(width, height, |x, y| {
+        let max = offsets
+            .iter()
+            .filter_map(|(dx, dy)| {
+                let sample_x = x as i32 + dx;
+                let sample_y = y as i32 + dy;
+                if sample_x < 0 || sample_y < 0 || sample_x >= width as i32 || sample_y >= height as i32 {
+                    None
+                } else {
+                    Some(mask.get_pixel(sample_x as u32, sample_y as u32).0[0])
+                }
+            })
+            .max()
+            .unwrap_or(0);
+        Luma([max])
+    })
+}
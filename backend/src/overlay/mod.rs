@@ -1,7 +1,24 @@
+mod blend;
+mod error;
+mod glyph_effects;
 mod iter;
+mod layer;
+mod mask;
 mod osd;
+mod parallel;
 mod srt;
+mod static_layer;
+mod stream;
+mod timeline;
+mod yuv;
 
-pub use iter::FrameOverlayIter;
-pub use osd::overlay_osd;
+pub use blend::BlendMode;
+pub use error::{FrameCompositeError, OverlayLayerError};
+pub use glyph_effects::{GlyphEffects, StyledCharacter, StyledCharacters};
+pub use iter::{FrameOverlayIter, FramePixelFormat};
+pub use layer::{LayerKind, OsdLayer, OverlayLayer, SrtLayer};
+pub use osd::{get_character_size, grid_offset, overlay_osd};
 pub use srt::overlay_srt_data;
+pub use static_layer::{ImageLayer, ImageLayerOptions, TextLayer, TextLayerOptions};
+pub use stream::OverlayStreamIter;
+pub use timeline::{frame_index_at, osd_frame_index, srt_frame_index};
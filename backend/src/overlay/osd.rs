@@ -1,38 +1,191 @@
-use image::{imageops::overlay, RgbaImage};
+use image::RgbaImage;
 
+use super::blend::{blend_image, BlendMode};
 use crate::{
-    font::{self, CharacterSize},
-    osd::{self, OsdOptions},
+    font::{self, CharacterSize, ScalingFilter},
+    osd::{self, OsdOptions, OsdScalingMode, GRID_HEIGHT, GRID_WIDTH},
+    overlay::GlyphEffects,
 };
 
-pub fn get_character_size(lines: u32) -> CharacterSize {
-    match lines {
-        540 => CharacterSize::Race,
-        720 => CharacterSize::Small,
-        1080 => CharacterSize::Large,
-        1440 => CharacterSize::XLarge,
-        2160 => CharacterSize::Ultra,
-        _ => CharacterSize::Large,
-    }
+/// Get the size of the OSD characters for a frame of the given dimensions. The size is derived from the number of
+/// grid cells that need to fit in the frame, so it also works for cropped and non-16:9 videos.
+pub fn get_character_size(width: u32, height: u32, osd_options: &OsdOptions) -> CharacterSize {
+    let aspect_ratio = CharacterSize::Large.width() as f32 / CharacterSize::Large.height() as f32;
+    let cell_width = width as f32 / GRID_WIDTH as f32;
+    let cell_height = height as f32 / GRID_HEIGHT as f32;
+
+    let (char_width, char_height) = match osd_options.scaling_mode {
+        OsdScalingMode::Fit => {
+            let char_height = cell_height.min(cell_width / aspect_ratio);
+            (char_height * aspect_ratio, char_height)
+        }
+        OsdScalingMode::Fill => {
+            let char_height = cell_height.max(cell_width / aspect_ratio);
+            (char_height * aspect_ratio, char_height)
+        }
+        OsdScalingMode::Stretch => (cell_width, cell_height),
+    };
+
+    let scaled = |size: f32| (size * osd_options.scale).round().max(1.0) as u32;
+    CharacterSize::from_dimensions(scaled(char_width), scaled(char_height))
+}
+
+/// Offset of the OSD grid from the edge of the frame along one axis, which centers the grid in the frame. With the fit
+/// and fill scaling modes the grid doesn't cover the frame exactly, so the unused or cropped space is divided over both
+/// sides.
+pub fn grid_offset(frame_size: u32, cells: usize, cell_size: u32) -> i32 {
+    (frame_size as i32 - cells as i32 * cell_size as i32) / 2
 }
 
 #[inline]
-pub fn overlay_osd(image: &mut RgbaImage, osd_frame: &osd::Frame, font: &font::FontFile, osd_options: &OsdOptions) {
-    // TODO: check if this can be run in parallel
-    let osd_character_size = get_character_size(image.height());
-    for character in &osd_frame.glyphs {
-        if character.index == 0 || osd_options.get_mask(&character.grid_position) {
-            continue;
+pub fn overlay_osd(
+    image: &mut RgbaImage,
+    osd_frame: &osd::Frame,
+    font: &font::FontFile,
+    osd_options: &OsdOptions,
+    scaling_filter: ScalingFilter,
+    blend_mode: BlendMode,
+) {
+    let osd_character_size = get_character_size(image.width(), image.height(), osd_options);
+    let effects = GlyphEffects::new(osd_options, image.height());
+    let characters = font.styled_characters(&osd_character_size, scaling_filter, &effects);
+    let origin_x = grid_offset(image.width(), GRID_WIDTH, osd_character_size.width()) + osd_options.position.x;
+    let origin_y = grid_offset(image.height(), GRID_HEIGHT, osd_character_size.height()) + osd_options.position.y;
+    let glyphs = osd_frame
+        .glyphs
+        .iter()
+        .filter(|glyph| glyph.index != 0 && !osd_options.get_mask(&glyph.grid_position))
+        .filter_map(|glyph| {
+            let character = font
+                .character_index(glyph.index, osd_options.color_page)
+                .and_then(|index| characters.get(index))?;
+            let x = origin_x + glyph.grid_position.x as i32 * osd_character_size.width() as i32;
+            let y = origin_y + glyph.grid_position.y as i32 * osd_character_size.height() as i32;
+            Some((character, x, y))
+        })
+        .collect::<Vec<_>>();
+
+    // Draw the outlines and shadows of all characters first so they don't cover neighboring characters
+    let margin = effects.margin() as i32;
+    for (character, x, y) in &glyphs {
+        if let Some(effects_layer) = character.effects_layer {
+            blend_image(image, effects_layer, x - margin, y - margin, blend_mode);
+        }
+    }
+    for (character, x, y) in &glyphs {
+        blend_image(image, character.character, *x, *y, blend_mode);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn standard_resolutions_use_standard_character_sizes() {
+        let osd_options = OsdOptions::default();
+        let test_cases = [
+            (960, 540, CharacterSize::Race),
+            (1280, 720, CharacterSize::Small),
+            (1920, 1080, CharacterSize::Large),
+            (1920, 1088, CharacterSize::Large),
+            (2560, 1440, CharacterSize::XLarge),
+            (3840, 2160, CharacterSize::Ultra),
+        ];
+        for (width, height, expected) in test_cases {
+            assert_eq!(get_character_size(width, height, &osd_options), expected);
         }
-        if let Some(character_image) = font.get_character(character.index as usize, &osd_character_size) {
-            let grid_position = &character.grid_position;
-            let (char_width, char_height) = character_image.dimensions();
-            overlay(
-                image,
-                &character_image,
-                (grid_position.x as i32 * char_width as i32 + osd_options.position.x).into(),
-                (grid_position.y as i32 * char_height as i32 + osd_options.position.y).into(),
-            )
+    }
+
+    #[test]
+    fn scaling_modes_for_4_3_video() {
+        let mut osd_options = OsdOptions::default();
+        let test_cases = [
+            (OsdScalingMode::Fit, CharacterSize::Custom { width: 27, height: 41 }),
+            (OsdScalingMode::Fill, CharacterSize::Large),
+            (OsdScalingMode::Stretch, CharacterSize::Custom { width: 27, height: 54 }),
+        ];
+        for (scaling_mode, expected) in test_cases {
+            osd_options.scaling_mode = scaling_mode;
+            assert_eq!(get_character_size(1440, 1080, &osd_options), expected);
         }
     }
+
+    /// Position of the top left pixel that is drawn for a glyph at `grid_position`. The font is opaque so the drawn
+    /// pixels start at the corner of the cell.
+    fn first_glyph_position(
+        width: u32,
+        height: u32,
+        grid_position: osd::GridPosition,
+        osd_options: &OsdOptions,
+    ) -> Option<(u32, u32)> {
+        let size = CharacterSize::Large;
+        let font_image = RgbaImage::from_pixel(size.width(), size.height() * 256, image::Rgba([255; 4]));
+        let mut bytes = Vec::new();
+        font_image
+            .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageOutputFormat::Png)
+            .unwrap();
+        let font_file = font::FontFile::from_bytes("font.png".into(), &bytes).unwrap();
+        let osd_frame = osd::Frame {
+            time_millis: 0,
+            glyphs: vec![osd::Glyph {
+                index: b'H' as u16,
+                grid_position,
+            }],
+        };
+
+        let mut image = RgbaImage::new(width, height);
+        overlay_osd(
+            &mut image,
+            &osd_frame,
+            &font_file,
+            osd_options,
+            ScalingFilter::Nearest,
+            BlendMode::default(),
+        );
+        image
+            .enumerate_pixels()
+            .find(|(_, _, pixel)| pixel.0[3] > 0)
+            .map(|(x, y, _)| (x, y))
+    }
+
+    #[test]
+    fn grid_is_centered_for_4_3_video() {
+        let mut osd_options = OsdOptions::default();
+        let top_left = osd::GridPosition::new(0, 0);
+
+        // 53 columns of 27 pixels leave 9 pixels, 20 rows of 41 pixels leave 260 pixels
+        osd_options.scaling_mode = OsdScalingMode::Fit;
+        assert_eq!(first_glyph_position(1440, 1080, top_left, &osd_options), Some((4, 130)));
+
+        // 53 columns of 36 pixels are 468 pixels wider than the video, so the grid is cropped on both sides
+        osd_options.scaling_mode = OsdScalingMode::Fill;
+        assert_eq!(grid_offset(1440, GRID_WIDTH, 36), -234);
+        assert_eq!(first_glyph_position(1440, 1080, top_left, &osd_options), None);
+        assert_eq!(
+            first_glyph_position(1440, 1080, osd::GridPosition::new(26, 0), &osd_options),
+            Some((702, 0))
+        );
+
+        // The position offset moves the centered grid
+        osd_options.scaling_mode = OsdScalingMode::Fit;
+        osd_options.position.x = 10;
+        osd_options.position.y = -30;
+        assert_eq!(
+            first_glyph_position(1440, 1080, top_left, &osd_options),
+            Some((14, 100))
+        );
+    }
+
+    #[test]
+    fn user_scale_factor() {
+        let osd_options = OsdOptions {
+            scale: 0.75,
+            ..Default::default()
+        };
+        assert_eq!(
+            get_character_size(1920, 1080, &osd_options),
+            CharacterSize::Custom { width: 27, height: 41 }
+        );
+    }
 }
@@ -0,0 +1,179 @@
+use std::{
+    any::Any,
+    collections::BTreeMap,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+};
+
+use crossbeam_channel::{Receiver, Sender};
+
+/// Maximum number of frames that are composited at the same time. Every frame in flight holds a full decoded video
+/// frame in memory, which is 33 MB for 4K video.
+const MAX_ITEMS_IN_FLIGHT: usize = 16;
+
+/// Capacity that keeps all threads of the rayon pool busy, limited to [`MAX_ITEMS_IN_FLIGHT`].
+pub fn default_capacity() -> usize {
+    (rayon::current_num_threads() + 1).min(MAX_ITEMS_IN_FLIGHT)
+}
+
+/// Mapping an item panicked. Contains the panic message.
+#[derive(Debug)]
+pub struct Panicked(pub String);
+
+/// Iterator adapter that maps the items of `input` on the rayon thread pool and yields the results in input order.
+///
+/// At most `capacity` items are mapped or waiting in the reorder buffer at any time, so a slow consumer or a single
+/// slow item doesn't cause the whole input to be read into memory. A panic while mapping an item is yielded as
+/// [`Panicked`] for that item.
+pub struct OrderedParallelMap<I: Iterator, U> {
+    input: I,
+    map: Arc<dyn Fn(I::Item) -> U + Send + Sync>,
+    capacity: usize,
+    submitted: usize,
+    next_index: usize,
+    input_finished: bool,
+    result_sender: Sender<(usize, Result<U, Panicked>)>,
+    result_receiver: Receiver<(usize, Result<U, Panicked>)>,
+    reorder_buffer: BTreeMap<usize, Result<U, Panicked>>,
+}
+
+impl<I, U> OrderedParallelMap<I, U>
+where
+    I: Iterator,
+    I::Item: Send + 'static,
+    U: Send + 'static,
+{
+    pub fn new(input: I, capacity: usize, map: impl Fn(I::Item) -> U + Send + Sync + 'static) -> Self {
+        let (result_sender, result_receiver) = crossbeam_channel::unbounded();
+        Self {
+            input,
+            map: Arc::new(map),
+            capacity: capacity.max(1),
+            submitted: 0,
+            next_index: 0,
+            input_finished: false,
+            result_sender,
+            result_receiver,
+            reorder_buffer: BTreeMap::new(),
+        }
+    }
+
+    fn submit(&mut self, item: I::Item) {
+        let index = self.submitted;
+        let map = self.map.clone();
+        let result_sender = self.result_sender.clone();
+        rayon::spawn(move || {
+            // A panic in a rayon job aborts the process, so it is caught and yielded in place of the result
+            let result = panic::catch_unwind(AssertUnwindSafe(|| map(item))).map_err(panic_message);
+            // Sending fails when the iterator was dropped, in which case the result isn't needed anymore
+            result_sender.send((index, result)).ok();
+        });
+        self.submitted += 1;
+    }
+}
+
+impl<I, U> Iterator for OrderedParallelMap<I, U>
+where
+    I: Iterator,
+    I::Item: Send + 'static,
+    U: Send + 'static,
+{
+    type Item = Result<U, Panicked>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.input_finished && self.submitted - self.next_index < self.capacity {
+            match self.input.next() {
+                Some(item) => self.submit(item),
+                None => self.input_finished = true,
+            }
+        }
+
+        if self.next_index == self.submitted {
+            return None;
+        }
+
+        loop {
+            if let Some(result) = self.reorder_buffer.remove(&self.next_index) {
+                self.next_index += 1;
+                return Some(result);
+            }
+            let (index, result) = self
+                .result_receiver
+                .recv()
+                .expect("Result sender is owned by the iterator");
+            self.reorder_buffer.insert(index, result);
+        }
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> Panicked {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "Unknown panic".to_string());
+    Panicked(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+        time::Duration,
+    };
+
+    use claims::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn results_are_yielded_in_input_order() {
+        // Earlier items take longer so they finish after later items
+        let results = OrderedParallelMap::new(0..32u64, 8, |i| {
+            thread::sleep(Duration::from_millis((32 - i) % 8));
+            i * 2
+        })
+        .map(Result::unwrap)
+        .collect::<Vec<_>>();
+
+        assert_eq!(results, (0..32).map(|i| i * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn panics_are_yielded_as_errors() {
+        let results = OrderedParallelMap::new(0..8, 4, |i| {
+            if i == 5 {
+                panic!("Item {i} is invalid");
+            }
+            i
+        })
+        .collect::<Vec<_>>();
+
+        assert_eq!(results.len(), 8);
+        assert!(results
+            .iter()
+            .enumerate()
+            .all(|(i, r)| i == 5 || r.as_ref().is_ok_and(|&r| r == i)));
+        assert_matches!(&results[5], Err(Panicked(message)) if message == "Item 5 is invalid");
+    }
+
+    #[test]
+    fn items_in_flight_are_bounded() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        let counter = in_flight.clone();
+        let max = max_in_flight.clone();
+        let results = OrderedParallelMap::new(0..64, 3, move |i| {
+            let current = counter.fetch_add(1, Ordering::SeqCst) + 1;
+            max.fetch_max(current, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(1));
+            counter.fetch_sub(1, Ordering::SeqCst);
+            i
+        });
+
+        assert_eq!(results.count(), 64);
+        assert!(max_in_flight.load(Ordering::SeqCst) <= 3);
+    }
+}
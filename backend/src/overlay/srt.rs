@@ -1,80 +1,252 @@
-use image::{Rgba, RgbaImage};
-use imageproc::drawing::draw_text_mut;
+use image::{GrayImage, Rgba, RgbaImage};
+use imageproc::filter::gaussian_blur_f32;
 
-use crate::srt::{SrtFrameData, SrtOptions};
+use super::{
+    blend::{blend_pixel, BlendMode},
+    mask::{dilate_mask, draw_mask},
+};
+use crate::srt::{field_style, SrtField, SrtFont, SrtFrameData, SrtOptions};
+
+const FIELD_SEPARATOR: &str = "  ";
+const BLINK_INTERVAL_SECS: f32 = 0.5;
+
+struct TextSegment {
+    text: String,
+    color: [f32; 3],
+    visible: bool,
+}
+
+/// Whether blinking SRT fields are visible at the given video time.
+pub(crate) fn blink_on(video_time_secs: f32) -> bool {
+    ((video_time_secs / BLINK_INTERVAL_SECS) as u32).is_multiple_of(2)
+}
 
 #[inline]
 pub fn overlay_srt_data(
     image: &mut RgbaImage,
     srt_data: &SrtFrameData,
-    font: &rusttype::Font,
+    font: &SrtFont,
     srt_options: &SrtOptions,
+    video_time_secs: f32,
+    blend_mode: BlendMode,
 ) {
-    let time_str = if srt_options.show_time {
-        let minutes = srt_data.flight_time / 60;
-        let seconds = srt_data.flight_time % 60;
-        format!("Time:{}:{:0>2}  ", minutes, seconds % 60)
-    } else {
-        "".into()
-    };
+    let blink_on = blink_on(video_time_secs);
+    let segments = SrtField::ALL
+        .iter()
+        .filter_map(|field| {
+            let style = field_style(*field, srt_data, srt_options);
+            style.visible.then(|| TextSegment {
+                text: field.format(srt_data, srt_options),
+                color: style.color,
+                visible: !style.blink || blink_on,
+            })
+        })
+        .collect::<Vec<_>>();
 
-    let sbat_str = if srt_options.show_sbat {
-        format!("SBat:{: >4.1}V  ", srt_data.sky_bat)
-    } else {
-        "".into()
-    };
+    let image_dimensions = image.dimensions();
 
-    let gbat_str = if srt_options.show_gbat {
-        format!("GBat:{: >4.1}V  ", srt_data.ground_bat)
-    } else {
-        "".into()
-    };
+    let x_pos = srt_options.position.x / 100.0 * image_dimensions.0 as f32;
+    let y_pos = srt_options.position.y / 100.0 * image_dimensions.1 as f32;
+    let scale_factor = image_dimensions.1 as f32 / 1080.0;
+    let scale = rusttype::Scale::uniform(srt_options.scale * scale_factor);
 
-    let signal_str = if srt_options.show_signal {
-        format!("Signal:{}  ", srt_data.signal)
-    } else {
-        "".into()
-    };
+    draw_styled_text(
+        image,
+        &segments,
+        x_pos as i32,
+        y_pos as i32,
+        scale,
+        scale_factor,
+        font,
+        srt_options,
+        blend_mode,
+    );
+}
+
+/// Draws the text segments with the optional background box, drop shadow and outline from the SRT options. All pixel
+/// sizes in the options are specified for 1080p video and scaled with `scale_factor`.
+fn draw_styled_text(
+    image: &mut RgbaImage,
+    segments: &[TextSegment],
+    x: i32,
+    y: i32,
+    scale: rusttype::Scale,
+    scale_factor: f32,
+    font: &SrtFont,
+    srt_options: &SrtOptions,
+    blend_mode: BlendMode,
+) {
+    let full_text = segments
+        .iter()
+        .map(|s| s.text.as_str())
+        .collect::<Vec<_>>()
+        .join(FIELD_SEPARATOR);
+    let (text_width, text_height) = font.text_size(scale, &full_text);
+    if text_width <= 0 || text_height <= 0 {
+        return;
+    }
+
+    // Horizontal offset of each segment from the start of the text
+    let mut prefix = String::new();
+    let segment_offsets = segments
+        .iter()
+        .map(|segment| {
+            let offset = font.text_advance(scale, &prefix).round() as i32;
+            prefix.push_str(&segment.text);
+            prefix.push_str(FIELD_SEPARATOR);
+            offset
+        })
+        .collect::<Vec<_>>();
 
-    let latency_str = if srt_options.show_latency {
-        format!("Latency:{: >3}ms  ", srt_data.latency)
+    if srt_options.show_background {
+        let padding = (scale.y * 0.25) as i32;
+        draw_rounded_rect(
+            image,
+            x - padding,
+            y - padding,
+            (text_width + 2 * padding) as u32,
+            (text_height + 2 * padding) as u32,
+            srt_options.background_corner_radius * scale_factor,
+            to_rgba(srt_options.background_color, srt_options.background_opacity),
+            blend_mode,
+        );
+    }
+
+    let outline_width = if srt_options.show_outline {
+        (srt_options.outline_width * scale_factor).round().max(0.0) as u32
     } else {
-        "".into()
+        0
     };
-
-    let bitrate_str = if srt_options.show_bitrate {
-        format!("Bitrate:{: >4.1}Mbps  ", srt_data.bitrate_mbps)
+    let shadow_blur = if srt_options.show_shadow {
+        (srt_options.shadow_blur * scale_factor).max(0.0)
     } else {
-        "".into()
+        0.0
     };
 
-    let distance_str = if srt_options.show_distance {
-        let distance = srt_data.distance;
-        if distance > 999 {
-            let km = distance as f32 / 1000.0;
-            format!("Distance:{:.2}km", km)
-        } else {
-            format!("Distance:{: >3}m", srt_data.distance)
+    // Leave room around the text in the mask for the outline and the blurred shadow
+    let margin = outline_width as i32 + (3.0 * shadow_blur).ceil() as i32;
+    let mask_width = (text_width + 2 * margin) as u32;
+    let mask_height = (text_height + 2 * margin) as u32;
+    let segment_masks = segments
+        .iter()
+        .zip(&segment_offsets)
+        .filter(|(segment, _)| segment.visible)
+        .map(|(segment, offset)| {
+            let mut mask = GrayImage::new(mask_width, mask_height);
+            font.draw_text_mask(&mut mask, margin + offset, margin, scale, &segment.text);
+            (mask, segment.color)
+        })
+        .collect::<Vec<_>>();
+    if segment_masks.is_empty() {
+        return;
+    }
+
+    let mut text_mask = GrayImage::new(mask_width, mask_height);
+    for (mask, _) in &segment_masks {
+        for (combined, segment) in text_mask.pixels_mut().zip(mask.pixels()) {
+            combined.0[0] = combined.0[0].max(segment.0[0]);
         }
+    }
+
+    let outline_mask = if outline_width > 0 {
+        Some(dilate_mask(&text_mask, outline_width))
     } else {
-        "".into()
+        None
     };
 
-    let srt_string = format!("{time_str}{sbat_str}{gbat_str}{signal_str}{latency_str}{bitrate_str}{distance_str}");
+    let mask_x = x - margin;
+    let mask_y = y - margin;
 
-    let image_dimensions = image.dimensions();
+    if srt_options.show_shadow {
+        let shadow_source = outline_mask.as_ref().unwrap_or(&text_mask);
+        let shadow_mask = if shadow_blur > 0.0 {
+            gaussian_blur_f32(shadow_source, shadow_blur)
+        } else {
+            shadow_source.clone()
+        };
+        draw_mask(
+            image,
+            &shadow_mask,
+            mask_x + (srt_options.shadow_offset.x * scale_factor) as i32,
+            mask_y + (srt_options.shadow_offset.y * scale_factor) as i32,
+            to_rgba(srt_options.shadow_color, srt_options.shadow_opacity),
+            blend_mode,
+        );
+    }
 
-    let x_pos = srt_options.position.x / 100.0 * image_dimensions.0 as f32;
-    let y_pos = srt_options.position.y / 100.0 * image_dimensions.1 as f32;
-    let scale = srt_options.scale / 1080.0 * image_dimensions.1 as f32;
+    if let Some(outline_mask) = &outline_mask {
+        draw_mask(
+            image,
+            outline_mask,
+            mask_x,
+            mask_y,
+            to_rgba(srt_options.outline_color, srt_options.text_opacity),
+            blend_mode,
+        );
+    }
 
-    draw_text_mut(
-        image,
-        Rgba([240u8, 240u8, 240u8, 10u8]),
-        x_pos as i32,
-        y_pos as i32,
-        rusttype::Scale::uniform(scale),
-        font,
-        &srt_string,
-    );
+    for (mask, color) in &segment_masks {
+        draw_mask(
+            image,
+            mask,
+            mask_x,
+            mask_y,
+            to_rgba(*color, srt_options.text_opacity),
+            blend_mode,
+        );
+    }
+}
+
+fn to_rgba(color: [f32; 3], opacity: f32) -> Rgba<u8> {
+    Rgba([
+        (color[0] * 255.0) as u8,
+        (color[1] * 255.0) as u8,
+        (color[2] * 255.0) as u8,
+        (opacity.clamp(0.0, 1.0) * 255.0) as u8,
+    ])
+}
+
+fn draw_rounded_rect(
+    image: &mut RgbaImage,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    radius: f32,
+    color: Rgba<u8>,
+    blend_mode: BlendMode,
+) {
+    let radius = radius.clamp(0.0, width.min(height) as f32 / 2.0);
+    let (image_width, image_height) = image.dimensions();
+    for rect_y in 0..height {
+        for rect_x in 0..width {
+            let image_x = x + rect_x as i32;
+            let image_y = y + rect_y as i32;
+            if image_x < 0 || image_y < 0 || image_x >= image_width as i32 || image_y >= image_height as i32 {
+                continue;
+            }
+
+            // Distance from the pixel center to the nearest corner circle center, only relevant in the corners
+            let px = rect_x as f32 + 0.5;
+            let py = rect_y as f32 + 0.5;
+            let dx = (radius - px).max(px - (width as f32 - radius)).max(0.0);
+            let dy = (radius - py).max(py - (height as f32 - radius)).max(0.0);
+            let coverage = if radius > 0.0 {
+                (radius - (dx * dx + dy * dy).sqrt() + 0.5).clamp(0.0, 1.0)
+            } else {
+                1.0
+            };
+            if coverage <= 0.0 {
+                continue;
+            }
+
+            let mut pixel_color = color;
+            pixel_color.0[3] = (color.0[3] as f32 * coverage) as u8;
+            blend_pixel(
+                image.get_pixel_mut(image_x as u32, image_y as u32),
+                &pixel_color,
+                blend_mode,
+            );
+        }
+    }
 }
@@ -0,0 +1,354 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use derivative::Derivative;
+use image::{GrayImage, Rgba, RgbaImage};
+use rusttype::Scale;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    blend::{blend_image, BlendMode},
+    error::OverlayLayerError,
+    layer::OverlayLayer,
+    mask::{dilate_mask, draw_mask},
+};
+use crate::{
+    font::{scale_character, CharacterSize, ScalingFilter},
+    srt::SrtFont,
+    util::Coordinates,
+};
+
+/// PNG image, like a logo, that is drawn on every frame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Derivative)]
+#[derivative(Default)]
+pub struct ImageLayerOptions {
+    pub path: PathBuf,
+    /// Position of the top left corner of the image in % of the video width and height.
+    #[derivative(Default(value = "Coordinates::new(2.0, 2.0)"))]
+    pub position: Coordinates<f32>,
+    /// Size of the image relative to its own size in a 1080p video.
+    #[derivative(Default(value = "1.0"))]
+    pub scale: f32,
+    #[derivative(Default(value = "1.0"))]
+    pub opacity: f32,
+}
+
+/// Static text, like the pilot name, that is drawn on every frame.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Derivative)]
+#[derivative(Default)]
+pub struct TextLayerOptions {
+    pub text: String,
+    /// TrueType or OpenType font, the bundled SRT font is used when not set.
+    pub font_path: Option<PathBuf>,
+    /// Position of the top left corner of the text in % of the video width and height.
+    #[derivative(Default(value = "Coordinates::new(2.0, 2.0)"))]
+    pub position: Coordinates<f32>,
+    /// Font size in pixels at 1080p.
+    #[derivative(Default(value = "35.0"))]
+    pub size: f32,
+    #[derivative(Default(value = "[1.0, 1.0, 1.0]"))]
+    pub color: [f32; 3],
+    #[derivative(Default(value = "true"))]
+    pub show_outline: bool,
+    /// Outline width in pixels at 1080p.
+    #[derivative(Default(value = "2.0"))]
+    pub outline_width: f32,
+    pub outline_color: [f32; 3],
+}
+
+/// Image that is drawn once for a frame height and then copied onto every frame of that height. Shared by the threads
+/// that composite the frames.
+#[derive(Default)]
+struct RenderedImage {
+    cached: RwLock<Option<(u32, Arc<RgbaImage>)>>,
+}
+
+impl RenderedImage {
+    fn get_or_render(&self, frame_height: u32, render: impl FnOnce() -> RgbaImage) -> Arc<RgbaImage> {
+        if let Some((height, image)) = &*self.cached.read().unwrap() {
+            if *height == frame_height {
+                return image.clone();
+            }
+        }
+        let image = Arc::new(render());
+        *self.cached.write().unwrap() = Some((frame_height, image.clone()));
+        image
+    }
+}
+
+pub struct ImageLayer {
+    image: Arc<RgbaImage>,
+    options: ImageLayerOptions,
+    blend_mode: BlendMode,
+    rendered: RenderedImage,
+}
+
+impl ImageLayer {
+    #[tracing::instrument(err)]
+    pub fn open(options: &ImageLayerOptions, blend_mode: BlendMode) -> Result<Self, OverlayLayerError> {
+        let image = image::open(&options.path)
+            .map_err(|source| OverlayLayerError::UnableToOpenImage {
+                path: options.path.clone(),
+                source,
+            })?
+            .into_rgba8();
+        Ok(Self {
+            image: Arc::new(image),
+            options: options.clone(),
+            blend_mode,
+            rendered: RenderedImage::default(),
+        })
+    }
+
+    /// Layer with other options. The image is only opened again when the path changed.
+    pub fn with_options(&self, options: &ImageLayerOptions, blend_mode: BlendMode) -> Result<Self, OverlayLayerError> {
+        if options.path != self.options.path {
+            return Self::open(options, blend_mode);
+        }
+        Ok(Self {
+            image: self.image.clone(),
+            options: options.clone(),
+            blend_mode,
+            rendered: RenderedImage::default(),
+        })
+    }
+
+    fn render(&self, frame_height: u32) -> RgbaImage {
+        let scale = self.options.scale.max(0.0) * frame_height as f32 / 1080.0;
+        let size = CharacterSize::Custom {
+            width: ((self.image.width() as f32 * scale).round() as u32).max(1),
+            height: ((self.image.height() as f32 * scale).round() as u32).max(1),
+        };
+        let mut scaled = scale_character(&self.image, &size, ScalingFilter::default());
+
+        let opacity = self.options.opacity.clamp(0.0, 1.0);
+        if opacity < 1.0 {
+            for pixel in scaled.pixels_mut() {
+                pixel.0[3] = (pixel.0[3] as f32 * opacity).round() as u8;
+            }
+        }
+        scaled
+    }
+}
+
+impl OverlayLayer for ImageLayer {
+    fn draw(&self, image: &mut RgbaImage, _video_time_secs: f32) {
+        let rendered = self
+            .rendered
+            .get_or_render(image.height(), || self.render(image.height()));
+        let (x, y) = position_in_frame(image, self.options.position);
+        blend_image(image, &rendered, x, y, self.blend_mode);
+    }
+
+    fn content_id(&self, _video_time_secs: f32) -> u64 {
+        0
+    }
+}
+
+pub struct TextLayer {
+    font: SrtFont,
+    options: TextLayerOptions,
+    blend_mode: BlendMode,
+    rendered: RenderedImage,
+}
+
+impl TextLayer {
+    #[tracing::instrument(err)]
+    pub fn open(options: &TextLayerOptions, blend_mode: BlendMode) -> Result<Self, OverlayLayerError> {
+        let font = match &options.font_path {
+            Some(font_path) => SrtFont::open(font_path.clone())?,
+            None => SrtFont::bundled(),
+        };
+        Ok(Self {
+            font,
+            options: options.clone(),
+            blend_mode,
+            rendered: RenderedImage::default(),
+        })
+    }
+
+    /// Layer with other options. The font is only opened again when the font path changed.
+    pub fn with_options(&self, options: &TextLayerOptions, blend_mode: BlendMode) -> Result<Self, OverlayLayerError> {
+        if options.font_path != self.options.font_path {
+            return Self::open(options, blend_mode);
+        }
+        Ok(Self {
+            font: self.font.clone(),
+            options: options.clone(),
+            blend_mode,
+            rendered: RenderedImage::default(),
+        })
+    }
+
+    /// Draws the text with its outline on a transparent image. The top left corner of the text is offset by the outline
+    /// width from the top left corner of the image.
+    fn render(&self, frame_height: u32) -> RgbaImage {
+        let scale_factor = frame_height as f32 / 1080.0;
+        let scale = Scale::uniform(self.options.size * scale_factor);
+        let (text_width, text_height) = self.font.text_size(scale, &self.options.text);
+        if text_width <= 0 || text_height <= 0 {
+            return RgbaImage::new(0, 0);
+        }
+
+        let outline_width = if self.options.show_outline {
+            (self.options.outline_width * scale_factor).round().max(0.0) as u32
+        } else {
+            0
+        };
+        let margin = outline_width as i32;
+        let mut text_mask = GrayImage::new((text_width + 2 * margin) as u32, (text_height + 2 * margin) as u32);
+        self.font
+            .draw_text_mask(&mut text_mask, margin, margin, scale, &self.options.text);
+
+        let mut rendered = RgbaImage::new(text_mask.width(), text_mask.height());
+        if outline_width > 0 {
+            let outline_mask = dilate_mask(&text_mask, outline_width);
+            draw_mask(
+                &mut rendered,
+                &outline_mask,
+                0,
+                0,
+                to_rgba(self.options.outline_color),
+                BlendMode::Srgb,
+            );
+        }
+        draw_mask(
+            &mut rendered,
+            &text_mask,
+            0,
+            0,
+            to_rgba(self.options.color),
+            BlendMode::Srgb,
+        );
+        rendered
+    }
+}
+
+impl OverlayLayer for TextLayer {
+    fn draw(&self, image: &mut RgbaImage, _video_time_secs: f32) {
+        let rendered = self
+            .rendered
+            .get_or_render(image.height(), || self.render(image.height()));
+        let (x, y) = position_in_frame(image, self.options.position);
+        let margin = if self.options.show_outline {
+            (self.options.outline_width * image.height() as f32 / 1080.0)
+                .round()
+                .max(0.0) as i32
+        } else {
+            0
+        };
+        blend_image(image, &rendered, x - margin, y - margin, self.blend_mode);
+    }
+
+    fn content_id(&self, _video_time_secs: f32) -> u64 {
+        0
+    }
+}
+
+fn position_in_frame(image: &RgbaImage, position: Coordinates<f32>) -> (i32, i32) {
+    (
+        (position.x / 100.0 * image.width() as f32) as i32,
+        (position.y / 100.0 * image.height() as f32) as i32,
+    )
+}
+
+fn to_rgba(color: [f32; 3]) -> Rgba<u8> {
+    let [r, g, b] = color.map(|c| (c * 255.0) as u8);
+    Rgba([r, g, b, 255])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image_layer(image: RgbaImage, options: ImageLayerOptions) -> ImageLayer {
+        ImageLayer {
+            image: Arc::new(image),
+            options,
+            blend_mode: BlendMode::Srgb,
+            rendered: RenderedImage::default(),
+        }
+    }
+
+    #[test]
+    fn image_is_scaled_with_frame_height_and_faded() {
+        let options = ImageLayerOptions {
+            position: Coordinates::new(50.0, 50.0),
+            scale: 0.5,
+            opacity: 0.5,
+            ..Default::default()
+        };
+        let layer = image_layer(RgbaImage::from_pixel(40, 20, Rgba([255, 0, 0, 255])), options);
+
+        // At 2160p the image is drawn at its own size
+        let mut frame = RgbaImage::from_pixel(200, 2160, Rgba([0, 0, 0, 255]));
+        layer.draw(&mut frame, 0.0);
+        let is_faded_red = |x, y| {
+            let [r, g, b, a] = frame.get_pixel(x, y).0;
+            (127..=128).contains(&r) && g == 0 && b == 0 && a >= 254
+        };
+        assert!(is_faded_red(100, 1080));
+        assert!(is_faded_red(139, 1099));
+        assert_eq!(*frame.get_pixel(99, 1080), Rgba([0, 0, 0, 255]));
+        assert_eq!(*frame.get_pixel(140, 1080), Rgba([0, 0, 0, 255]));
+        assert_eq!(*frame.get_pixel(100, 1100), Rgba([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn missing_image_is_an_error() {
+        let options = ImageLayerOptions {
+            path: PathBuf::from("does/not/exist.png"),
+            ..Default::default()
+        };
+        assert!(matches!(
+            ImageLayer::open(&options, BlendMode::Srgb),
+            Err(OverlayLayerError::UnableToOpenImage { .. })
+        ));
+    }
+
+    #[test]
+    fn image_is_reused_when_path_is_unchanged() {
+        let options = ImageLayerOptions {
+            path: PathBuf::from("does/not/exist.png"),
+            ..Default::default()
+        };
+        let layer = image_layer(RgbaImage::new(4, 4), options.clone());
+
+        let scaled = ImageLayerOptions { scale: 2.0, ..options };
+        let reused = layer.with_options(&scaled, BlendMode::Srgb).unwrap();
+        assert!(Arc::ptr_eq(&layer.image, &reused.image));
+        assert_eq!(reused.options.scale, 2.0);
+
+        let moved = ImageLayerOptions {
+            path: PathBuf::from("does/not/exist/either.png"),
+            ..scaled
+        };
+        assert!(matches!(
+            layer.with_options(&moved, BlendMode::Srgb),
+            Err(OverlayLayerError::UnableToOpenImage { .. })
+        ));
+    }
+
+    #[test]
+    fn text_is_drawn_with_outline() {
+        let options = TextLayerOptions {
+            text: "Pilot".to_string(),
+            position: Coordinates::new(10.0, 10.0),
+            outline_color: [0.0, 0.0, 1.0],
+            ..Default::default()
+        };
+        let layer = TextLayer::open(&options, BlendMode::Srgb).unwrap();
+
+        let mut frame = RgbaImage::new(640, 360);
+        layer.draw(&mut frame, 0.0);
+        assert!(frame.pixels().any(|p| p.0 == [255, 255, 255, 255]));
+        assert!(frame.pixels().any(|p| p.0 == [0, 0, 255, 255]));
+        // Nothing is drawn above or to the left of the position, except the one pixel outline
+        assert!(frame
+            .enumerate_pixels()
+            .filter(|(_, _, p)| p.0[3] > 0)
+            .all(|(x, y, _)| x >= 63 && y >= 35));
+    }
+}
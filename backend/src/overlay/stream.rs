@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use image::RgbaImage;
+
+use super::{iter::FrameCompositor, layer::OverlayLayer};
+
+/// Iterator over transparent frames that only contain the overlay layers, at a constant frame rate. The frames are
+/// composited onto the video by ffmpeg so the video itself never has to be decoded by the tool.
+///
+/// A frame is only drawn when the content of one of the layers changes. In between the previous frame is yielded
+/// again.
+pub struct OverlayStreamIter {
+    width: u32,
+    height: u32,
+    frame_rate: f32,
+    frame_count: u32,
+    frame_index: u32,
+    compositor: FrameCompositor,
+    current_frame: Option<(Vec<u64>, Arc<RgbaImage>)>,
+}
+
+impl OverlayStreamIter {
+    #[tracing::instrument(skip(layers), level = "debug")]
+    pub fn new(width: u32, height: u32, frame_rate: f32, frame_count: u32, layers: Vec<Box<dyn OverlayLayer>>) -> Self {
+        Self {
+            width,
+            height,
+            frame_rate,
+            frame_count,
+            frame_index: 0,
+            compositor: FrameCompositor {
+                layers,
+                background: None,
+            },
+            current_frame: None,
+        }
+    }
+}
+
+impl Iterator for OverlayStreamIter {
+    type Item = Arc<RgbaImage>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.frame_index >= self.frame_count {
+            return None;
+        }
+
+        let video_time_secs = self.frame_index as f32 / self.frame_rate;
+        self.frame_index += 1;
+
+        let content_ids = self.compositor.content_ids(video_time_secs);
+        match &self.current_frame {
+            Some((current_ids, frame)) if *current_ids == content_ids => Some(frame.clone()),
+            _ => {
+                let mut frame = RgbaImage::new(self.width, self.height);
+                self.compositor.draw(&mut frame, video_time_secs);
+                let frame = Arc::new(frame);
+                self.current_frame = Some((content_ids, frame.clone()));
+                Some(frame)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        font::{FontFile, ScalingFilter},
+        osd::{self, OsdOptions},
+        overlay::{BlendMode, OsdLayer},
+    };
+
+    fn osd_frame(time_millis: u32, glyph_index: u16) -> osd::Frame {
+        osd::Frame {
+            time_millis,
+            glyphs: vec![osd::Glyph {
+                index: glyph_index,
+                grid_position: osd::GridPosition { x: 1, y: 1 },
+            }],
+        }
+    }
+
+    #[test]
+    fn frames_are_only_drawn_when_the_osd_changes() {
+        let osd_layer = OsdLayer::new(
+            vec![osd_frame(0, 65), osd_frame(100, 66)],
+            FontFile::bundled_text(),
+            &OsdOptions::default(),
+            ScalingFilter::default(),
+            (320, 180),
+            BlendMode::default(),
+        );
+        let frames = OverlayStreamIter::new(320, 180, 20.0, 6, vec![Box::new(osd_layer)]).collect::<Vec<_>>();
+
+        // The second OSD frame starts after 0.1 s, which is the third video frame at 20 fps
+        assert_eq!(frames.len(), 6);
+        assert!(Arc::ptr_eq(&frames[0], &frames[2]));
+        assert!(!Arc::ptr_eq(&frames[2], &frames[3]));
+        assert!(Arc::ptr_eq(&frames[3], &frames[5]));
+        assert_ne!(frames[0].as_raw(), frames[3].as_raw());
+
+        // Pixels without OSD are transparent
+        assert_eq!(frames[0].get_pixel(0, 0).0[3], 0);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{osd, srt::SrtFrame};
+
+/// Index of the frame that is shown at `video_time_secs`, in frames that are sorted by start time. This is the last
+/// frame that started before the video time, or the first frame when the video time is before the first frame.
+/// Returns `None` when there are no frames.
+///
+/// The lookup doesn't depend on previously shown frames, so frames can be looked up in any order, for example when the
+/// preview jumps to another point in the video or a render starts in the middle of the video.
+pub fn frame_index_at<T>(frames: &[T], video_time_secs: f32, start_time_secs: impl Fn(&T) -> f32) -> Option<usize> {
+    if frames.is_empty() {
+        return None;
+    }
+    Some(
+        frames
+            .partition_point(|frame| start_time_secs(frame) < video_time_secs)
+            .saturating_sub(1),
+    )
+}
+
+/// Index of the OSD frame that is shown at `video_time_secs`. OSD times are multiplied by the playback speed factor
+/// to match the video time.
+pub fn osd_frame_index(frames: &[osd::Frame], playback_speed_factor: f32, video_time_secs: f32) -> Option<usize> {
+    frame_index_at(frames, video_time_secs, |frame| {
+        frame.time_millis as f32 / 1000.0 * playback_speed_factor
+    })
+}
+
+/// Index of the SRT frame that is shown at `video_time_secs`.
+pub fn srt_frame_index(frames: &[SrtFrame], video_time_secs: f32) -> Option<usize> {
+    frame_index_at(frames, video_time_secs, |frame| frame.start_time_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn osd_frames(frame_times_millis: impl IntoIterator<Item = u32>) -> Vec<osd::Frame> {
+        frame_times_millis
+            .into_iter()
+            .map(|time_millis| osd::Frame {
+                time_millis,
+                glyphs: Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn no_frames() {
+        assert_eq!(osd_frame_index(&[], 1.0, 1.0), None);
+        assert_eq!(srt_frame_index(&[], 1.0), None);
+    }
+
+    #[test]
+    fn osd_faster_than_video_doesnt_lag_behind() {
+        // 200 Hz OSD against 100 fps video: every video frame skips an OSD frame
+        let frames = osd_frames((0..2000).map(|i| i * 5));
+        for video_frame in 1..1000 {
+            let video_time_secs = video_frame as f32 / 100.0;
+            let index = osd_frame_index(&frames, 1.0, video_time_secs).unwrap();
+            assert!(frames[index].time_millis as f32 / 1000.0 < video_time_secs);
+            assert!(frames[index + 1].time_millis as f32 / 1000.0 >= video_time_secs);
+        }
+    }
+
+    #[test]
+    fn frames_can_be_looked_up_in_any_order() {
+        let frames = osd_frames([0, 100, 200, 300]);
+        assert_eq!(osd_frame_index(&frames, 1.0, 10.0), Some(3));
+        assert_eq!(osd_frame_index(&frames, 1.0, 0.15), Some(1));
+        assert_eq!(osd_frame_index(&frames, 1.0, 0.0), Some(0));
+        // At half speed the OSD frames are shown twice as long
+        assert_eq!(osd_frame_index(&frames, 2.0, 0.25), Some(1));
+    }
+
+    #[test]
+    fn srt_frame_before_first_frame() {
+        let frames = [1.0, 2.0]
+            .map(|start_time_secs| SrtFrame {
+                start_time_secs,
+                end_time_secs: start_time_secs + 1.0,
+                data: None,
+                debug_data: None,
+            })
+            .to_vec();
+        assert_eq!(srt_frame_index(&frames, 0.5), Some(0));
+        assert_eq!(srt_frame_index(&frames, 1.5), Some(0));
+        assert_eq!(srt_frame_index(&frames, 2.5), Some(1));
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use ffmpeg_sidecar::event::OutputVideoFrame;
+use image::{Rgba, RgbaImage};
+
+use super::{error::FrameCompositeError, iter::FrameCompositor};
+
+/// Number of converted overlays that are kept. Frames are composited in parallel so neighboring frames with different
+/// overlays are in flight at the same time.
+const OVERLAY_CACHE_SIZE: usize = 4;
+
+thread_local! {
+    /// Buffer the layers are drawn on before the overlay is converted to YUV, reused by every frame on the thread.
+    static OVERLAY_BUFFER: RefCell<RgbaImage> = RefCell::new(RgbaImage::new(0, 0));
+}
+
+/// Convert a color to BT.709 limited range YUV, the color space of HD video.
+fn rgb_to_yuv([r, g, b]: [u8; 3]) -> [u8; 3] {
+    let [r, g, b] = [r, g, b].map(|c| c as f32 / 255.0);
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let u = (b - y) / 1.8556;
+    let v = (r - y) / 1.5748;
+    [16.0 + 219.0 * y, 128.0 + 224.0 * u, 128.0 + 224.0 * v].map(|c| c.round().clamp(0.0, 255.0) as u8)
+}
+
+#[inline]
+fn blend(dst: &mut u8, src: u8, alpha: u8) {
+    let alpha = alpha as u32;
+    *dst = ((*dst as u32 * (255 - alpha) + src as u32 * alpha + 127) / 255) as u8;
+}
+
+/// Sizes of the planes of a yuv420p frame: the luma plane, the width of the chroma planes and the size of each chroma
+/// plane.
+fn plane_sizes(width: u32, height: u32) -> (usize, usize, usize) {
+    let chroma_width = width.div_ceil(2) as usize;
+    let chroma_height = height.div_ceil(2) as usize;
+    ((width * height) as usize, chroma_width, chroma_width * chroma_height)
+}
+
+struct LumaSample {
+    offset: u32,
+    y: u8,
+    alpha: u8,
+}
+
+struct ChromaSample {
+    offset: u32,
+    u: u8,
+    v: u8,
+    alpha: u8,
+}
+
+/// Overlay converted to the planes of a yuv420p frame. Only pixels that aren't fully transparent are stored, so
+/// blending it into a frame only touches the pixels of the glyphs.
+pub(super) struct YuvOverlay {
+    width: u32,
+    height: u32,
+    luma: Vec<LumaSample>,
+    chroma: Vec<ChromaSample>,
+}
+
+impl YuvOverlay {
+    pub(super) fn from_rgba(overlay: &RgbaImage) -> Self {
+        let (width, height) = overlay.dimensions();
+        let (_, chroma_width, _) = plane_sizes(width, height);
+
+        let mut luma = Vec::new();
+        let mut chroma = Vec::new();
+        for block_y in 0..height.div_ceil(2) {
+            for block_x in 0..width.div_ceil(2) {
+                // Chroma is the alpha weighted average of the 2x2 block, the alpha is the average over the block
+                let (mut pixel_count, mut alpha_sum, mut u_sum, mut v_sum) = (0, 0, 0, 0);
+                for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(dx, dy)| (2 * block_x + dx, 2 * block_y + dy)) {
+                    if x >= width || y >= height {
+                        continue;
+                    }
+                    pixel_count += 1;
+                    let [r, g, b, alpha] = overlay.get_pixel(x, y).0;
+                    if alpha == 0 {
+                        continue;
+                    }
+                    let [y_value, u, v] = rgb_to_yuv([r, g, b]);
+                    luma.push(LumaSample {
+                        offset: y * width + x,
+                        y: y_value,
+                        alpha,
+                    });
+                    alpha_sum += alpha as u32;
+                    u_sum += u as u32 * alpha as u32;
+                    v_sum += v as u32 * alpha as u32;
+                }
+                if alpha_sum == 0 {
+                    continue;
+                }
+                chroma.push(ChromaSample {
+                    offset: block_y * chroma_width as u32 + block_x,
+                    u: ((u_sum + alpha_sum / 2) / alpha_sum) as u8,
+                    v: ((v_sum + alpha_sum / 2) / alpha_sum) as u8,
+                    alpha: ((alpha_sum + pixel_count / 2) / pixel_count) as u8,
+                });
+            }
+        }
+
+        Self {
+            width,
+            height,
+            luma,
+            chroma,
+        }
+    }
+
+    /// Blend the overlay into a yuv420p frame of the same size.
+    pub(super) fn blend_into(&self, frame: &mut [u8]) {
+        let (luma_size, _, chroma_size) = plane_sizes(self.width, self.height);
+        if frame.len() < luma_size + 2 * chroma_size {
+            tracing::warn!(
+                "Video frame is smaller than a {}x{} yuv420p frame",
+                self.width,
+                self.height
+            );
+            return;
+        }
+
+        let (y_plane, chroma_planes) = frame.split_at_mut(luma_size);
+        let (u_plane, v_plane) = chroma_planes.split_at_mut(chroma_size);
+        for sample in &self.luma {
+            blend(&mut y_plane[sample.offset as usize], sample.y, sample.alpha);
+        }
+        for sample in &self.chroma {
+            blend(&mut u_plane[sample.offset as usize], sample.u, sample.alpha);
+            blend(&mut v_plane[sample.offset as usize], sample.v, sample.alpha);
+        }
+    }
+}
+
+/// Fill a yuv420p frame with a single color, for chroma keying.
+fn fill_yuv420p(frame: &mut Vec<u8>, width: u32, height: u32, color: Rgba<u8>) {
+    let (luma_size, _, chroma_size) = plane_sizes(width, height);
+    let [y, u, v] = rgb_to_yuv([color.0[0], color.0[1], color.0[2]]);
+    frame.resize(luma_size + 2 * chroma_size, 0);
+    frame[..luma_size].fill(y);
+    frame[luma_size..luma_size + chroma_size].fill(u);
+    frame[luma_size + chroma_size..].fill(v);
+}
+
+/// Composites the overlay layers onto decoded yuv420p frames. The layers are drawn and converted to YUV once for every
+/// change of their content and blended into the planes of the frame in place.
+pub(super) struct YuvCompositor {
+    compositor: FrameCompositor,
+    overlays: Mutex<VecDeque<(Vec<u64>, Arc<YuvOverlay>)>>,
+}
+
+impl YuvCompositor {
+    pub(super) fn new(compositor: FrameCompositor) -> Self {
+        Self {
+            compositor,
+            overlays: Mutex::new(VecDeque::with_capacity(OVERLAY_CACHE_SIZE)),
+        }
+    }
+
+    pub(super) fn composite(&self, mut video_frame: OutputVideoFrame) -> Result<OutputVideoFrame, FrameCompositeError> {
+        let (luma_size, _, chroma_size) = plane_sizes(video_frame.width, video_frame.height);
+        let expected = luma_size + 2 * chroma_size;
+        if video_frame.data.len() != expected {
+            return Err(FrameCompositeError::InvalidFrameSize {
+                width: video_frame.width,
+                height: video_frame.height,
+                expected,
+                actual: video_frame.data.len(),
+            });
+        }
+
+        if let Some(background) = self.compositor.background {
+            fill_yuv420p(&mut video_frame.data, video_frame.width, video_frame.height, background);
+        }
+        self.overlay(video_frame.width, video_frame.height, video_frame.timestamp)
+            .blend_into(&mut video_frame.data);
+        Ok(video_frame)
+    }
+
+    fn overlay(&self, width: u32, height: u32, video_time_secs: f32) -> Arc<YuvOverlay> {
+        let content_ids = self.compositor.content_ids(video_time_secs);
+        if let Some((_, overlay)) = self
+            .overlays
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(ids, overlay)| *ids == content_ids && (overlay.width, overlay.height) == (width, height))
+        {
+            return overlay.clone();
+        }
+
+        // Draw without holding the lock so other threads can use the cached overlays in the meantime
+        let overlay = OVERLAY_BUFFER.with(|buffer| {
+            let mut buffer = buffer.borrow_mut();
+            if buffer.dimensions() == (width, height) {
+                buffer.fill(0);
+            } else {
+                *buffer = RgbaImage::new(width, height);
+            }
+            self.compositor.draw(&mut buffer, video_time_secs);
+            Arc::new(YuvOverlay::from_rgba(&buffer))
+        });
+
+        let mut overlays = self.overlays.lock().unwrap();
+        if overlays.len() == OVERLAY_CACHE_SIZE {
+            overlays.pop_front();
+        }
+        overlays.push_back((content_ids, overlay.clone()));
+        overlay
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray_frame(width: u32, height: u32) -> Vec<u8> {
+        let (luma_size, _, chroma_size) = plane_sizes(width, height);
+        let mut frame = vec![126; luma_size];
+        frame.resize(luma_size + 2 * chroma_size, 128);
+        frame
+    }
+
+    #[test]
+    fn bt709_colors() {
+        assert_eq!(rgb_to_yuv([0, 0, 0]), [16, 128, 128]);
+        assert_eq!(rgb_to_yuv([255, 255, 255]), [235, 128, 128]);
+        assert_eq!(rgb_to_yuv([255, 0, 0]), [63, 102, 240]);
+    }
+
+    #[test]
+    fn only_covered_pixels_are_blended() {
+        let mut overlay = RgbaImage::new(4, 4);
+        overlay.put_pixel(1, 1, Rgba([255, 255, 255, 255]));
+        overlay.put_pixel(2, 2, Rgba([0, 0, 0, 128]));
+        let overlay = YuvOverlay::from_rgba(&overlay);
+
+        let mut frame = gray_frame(4, 4);
+        overlay.blend_into(&mut frame);
+        let (y_plane, chroma_planes) = frame.split_at(16);
+        assert_eq!(y_plane[4 + 1], 235);
+        assert_eq!(y_plane[2 * 4 + 2], 71);
+        assert_eq!(y_plane.iter().filter(|&&y| y == 126).count(), 14);
+        // White and black don't change the chroma
+        assert!(chroma_planes.iter().all(|&c| c == 128));
+    }
+
+    #[test]
+    fn chroma_is_averaged_over_block() {
+        // One red pixel covers a quarter of the top left chroma block
+        let mut overlay = RgbaImage::new(4, 2);
+        overlay.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        let overlay = YuvOverlay::from_rgba(&overlay);
+
+        let mut frame = gray_frame(4, 2);
+        overlay.blend_into(&mut frame);
+        let (u_plane, v_plane) = frame[8..].split_at(2);
+        // A quarter of the way from gray to the chroma of red
+        assert_eq!(u_plane, [121, 128]);
+        assert_eq!(v_plane, [156, 128]);
+    }
+
+    #[test]
+    fn chroma_key_fills_all_planes() {
+        let mut frame = Vec::new();
+        fill_yuv420p(&mut frame, 4, 2, Rgba([255, 255, 255, 255]));
+        assert_eq!(frame, [235, 235, 235, 235, 235, 235, 235, 235, 128, 128, 128, 128]);
+    }
+}
@@ -15,3 +15,15 @@ pub enum SrtFileError {
         source: srtparse::ReaderError,
     },
 }
+
+#[derive(Debug, Error)]
+pub enum SrtFontError {
+    #[error("Unable to open SRT font file, source: {source}")]
+    UnableToOpenFile {
+        #[from]
+        source: std::io::Error,
+    },
+
+    #[error("Invalid SRT font file, expected a TrueType or OpenType font")]
+    InvalidFontFile,
+}
@@ -0,0 +1,164 @@
+use std::{fs, path::PathBuf};
+
+use derivative::Derivative;
+use image::{GrayImage, Luma};
+use rusttype::{point, Font, GlyphId, PositionedGlyph, Scale};
+
+use super::error::SrtFontError;
+
+const BUNDLED_FONT_NAME: &str = "AzeretMono (bundled)";
+const BUNDLED_FONT_BYTES: &[u8] = include_bytes!("../../../resources/fonts/AzeretMono-Regular.ttf");
+
+/// Font used to render the SRT data. Characters that are missing from the user selected font are looked up in the
+/// bundled font so the text is always complete.
+#[derive(Derivative, Clone)]
+#[derivative(Debug)]
+pub struct SrtFont {
+    pub file_path: Option<PathBuf>,
+    #[derivative(Debug = "ignore")]
+    fonts: Vec<(String, Font<'static>)>,
+}
+
+impl SrtFont {
+    pub fn bundled() -> Self {
+        Self {
+            file_path: None,
+            fonts: vec![(BUNDLED_FONT_NAME.to_string(), bundled_font())],
+        }
+    }
+
+    #[tracing::instrument(ret, err)]
+    pub fn open(path: PathBuf) -> Result<Self, SrtFontError> {
+        let bytes = fs::read(&path)?;
+        let font = Font::try_from_vec(bytes).ok_or(SrtFontError::InvalidFontFile)?;
+        if font.glyph_count() == 0 {
+            return Err(SrtFontError::InvalidFontFile);
+        }
+
+        let name = path
+            .file_name()
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Ok(Self {
+            fonts: vec![(name, font), (BUNDLED_FONT_NAME.to_string(), bundled_font())],
+            file_path: Some(path),
+        })
+    }
+
+    /// Names of the fonts in the order in which they are searched for a character.
+    pub fn fallback_chain(&self) -> Vec<&str> {
+        self.fonts.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    /// Get the width and height of the text when rendered with the given scale.
+    pub fn text_size(&self, scale: Scale, text: &str) -> (i32, i32) {
+        self.layout(scale, text)
+            .iter()
+            .filter_map(|g| g.pixel_bounding_box())
+            .fold((0, 0), |(width, height), bb| {
+                (width.max(bb.max.x), height.max(bb.max.y))
+            })
+    }
+
+    /// Get the horizontal distance from the start of the text to where the next character would be placed.
+    pub fn text_advance(&self, scale: Scale, text: &str) -> f32 {
+        self.layout(scale, text)
+            .last()
+            .map(|g| g.position().x + g.unpositioned().h_metrics().advance_width)
+            .unwrap_or(0.0)
+    }
+
+    /// Draws the text coverage into a mask with the top left corner of the text at `x`, `y`.
+    pub fn draw_text_mask(&self, mask: &mut GrayImage, x: i32, y: i32, scale: Scale, text: &str) {
+        let (mask_width, mask_height) = mask.dimensions();
+        for glyph in self.layout(scale, text) {
+            if let Some(bb) = glyph.pixel_bounding_box() {
+                glyph.draw(|gx, gy, coverage| {
+                    let mask_x = x + bb.min.x + gx as i32;
+                    let mask_y = y + bb.min.y + gy as i32;
+                    if mask_x >= 0 && mask_y >= 0 && (mask_x as u32) < mask_width && (mask_y as u32) < mask_height {
+                        let pixel = mask.get_pixel_mut(mask_x as u32, mask_y as u32);
+                        let value = (coverage * 255.0) as u8;
+                        *pixel = Luma([pixel.0[0].max(value)]);
+                    }
+                });
+            }
+        }
+    }
+
+    fn font_for_char(&self, c: char) -> &Font<'static> {
+        self.fonts
+            .iter()
+            .map(|(_, font)| font)
+            .find(|font| font.glyph(c).id() != GlyphId(0))
+            .unwrap_or(&self.fonts[0].1)
+    }
+
+    fn layout(&self, scale: Scale, text: &str) -> Vec<PositionedGlyph<'static>> {
+        let ascent = self.fonts[0].1.v_metrics(scale).ascent;
+        let mut caret = 0.0;
+        let mut previous: Option<(&Font<'static>, GlyphId)> = None;
+
+        text.chars()
+            .filter(|c| !c.is_control())
+            .map(|c| {
+                let font = self.font_for_char(c);
+                let glyph = font.glyph(c).scaled(scale);
+                if let Some((previous_font, previous_id)) = previous {
+                    if std::ptr::eq(previous_font, font) {
+                        caret += font.pair_kerning(scale, previous_id, glyph.id());
+                    }
+                }
+                previous = Some((font, glyph.id()));
+                let advance_width = glyph.h_metrics().advance_width;
+                let positioned = glyph.positioned(point(caret, ascent));
+                caret += advance_width;
+                positioned
+            })
+            .collect()
+    }
+}
+
+impl Default for SrtFont {
+    fn default() -> Self {
+        Self::bundled()
+    }
+}
+
+fn bundled_font() -> Font<'static> {
+    Font::try_from_bytes(BUNDLED_FONT_BYTES).expect("Bundled SRT font is invalid")
+}
+
+#[cfg(test)]
+mod tests {
+    use claims::{assert_err, assert_matches};
+
+    use super::*;
+
+    #[test]
+    fn reject_invalid_font_file() {
+        let path = std::env::temp_dir().join("walksnail-osd-tool-invalid-font.ttf");
+        fs::write(&path, b"not a font").unwrap();
+        let result = SrtFont::open(path.clone());
+        fs::remove_file(path).ok();
+        assert_matches!(result, Err(SrtFontError::InvalidFontFile));
+    }
+
+    #[test]
+    fn reject_missing_font_file() {
+        assert_err!(SrtFont::open(PathBuf::from("this/font/does/not/exist.ttf")));
+    }
+
+    #[test]
+    fn bundled_font_renders_text() {
+        let font = SrtFont::bundled();
+        let scale = Scale::uniform(30.0);
+        let (width, height) = font.text_size(scale, "Signal:4");
+        assert!(width > 0 && height > 0);
+
+        let mut mask = GrayImage::new(width as u32, height as u32);
+        font.draw_text_mask(&mut mask, 0, 0, scale, "Signal:4");
+        assert!(mask.pixels().any(|p| p.0[0] > 0));
+    }
+}
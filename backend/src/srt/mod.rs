@@ -1,8 +1,15 @@
 mod error;
+mod font;
 mod frame;
 mod options;
+mod rules;
 mod srt_file;
+mod units;
 
+pub use error::SrtFontError;
+pub use font::SrtFont;
 pub use frame::{SrtFrame, SrtFrameData};
 pub use options::SrtOptions;
+pub use rules::{field_style, Comparison, FieldStyle, SrtField, StyleRule, Visibility};
 pub use srt_file::SrtFile;
+pub use units::{format_voltage, UnitSystem};
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+use super::{rules::StyleRule, units::UnitSystem};
 use crate::util::Coordinates;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,6 +14,32 @@ pub struct SrtOptions {
     pub show_latency: bool,
     pub show_bitrate: bool,
     pub show_distance: bool,
+    pub unit_system: UnitSystem,
+    pub decimal_places: usize,
+    pub show_cell_voltage: bool,
+    pub cell_count: u8,
+    pub text_color: [f32; 3],
+    pub text_opacity: f32,
+    pub show_outline: bool,
+    pub outline_width: f32,
+    pub outline_color: [f32; 3],
+    pub show_shadow: bool,
+    pub shadow_offset: Coordinates<f32>,
+    pub shadow_blur: f32,
+    pub shadow_color: [f32; 3],
+    pub shadow_opacity: f32,
+    pub show_background: bool,
+    pub background_color: [f32; 3],
+    pub background_opacity: f32,
+    pub background_corner_radius: f32,
+    pub rules: Vec<StyleRule>,
+}
+
+impl SrtOptions {
+    /// Number of cells the sky battery voltage is divided over, if per-cell voltage is enabled.
+    pub fn sky_bat_cells(&self) -> Option<u8> {
+        self.show_cell_voltage.then_some(self.cell_count)
+    }
 }
 
 impl Default for SrtOptions {
@@ -27,6 +54,25 @@ impl Default for SrtOptions {
             show_latency: true,
             show_bitrate: true,
             show_distance: true,
+            unit_system: UnitSystem::Metric,
+            decimal_places: 1,
+            show_cell_voltage: false,
+            cell_count: 4,
+            text_color: [240.0 / 255.0, 240.0 / 255.0, 240.0 / 255.0],
+            text_opacity: 1.0,
+            show_outline: false,
+            outline_width: 2.0,
+            outline_color: [0.0, 0.0, 0.0],
+            show_shadow: false,
+            shadow_offset: Coordinates::new(3.0, 3.0),
+            shadow_blur: 2.0,
+            shadow_color: [0.0, 0.0, 0.0],
+            shadow_opacity: 0.6,
+            show_background: false,
+            background_color: [0.0, 0.0, 0.0],
+            background_opacity: 0.4,
+            background_corner_radius: 8.0,
+            rules: Vec::new(),
         }
     }
 }
@@ -0,0 +1,337 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use super::{format_voltage, SrtFrameData, SrtOptions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SrtField {
+    Time,
+    SkyBat,
+    GroundBat,
+    Signal,
+    Latency,
+    Bitrate,
+    Distance,
+}
+
+impl SrtField {
+    pub const ALL: [SrtField; 7] = [
+        SrtField::Time,
+        SrtField::SkyBat,
+        SrtField::GroundBat,
+        SrtField::Signal,
+        SrtField::Latency,
+        SrtField::Bitrate,
+        SrtField::Distance,
+    ];
+
+    /// Value of the field in the units it is displayed in, so thresholds match what the viewer sees.
+    pub fn value(&self, data: &SrtFrameData, options: &SrtOptions) -> f32 {
+        match self {
+            SrtField::Time => data.flight_time as f32,
+            SrtField::SkyBat => match options.sky_bat_cells() {
+                Some(cells) if cells > 0 => data.sky_bat / cells as f32,
+                _ => data.sky_bat,
+            },
+            SrtField::GroundBat => data.ground_bat,
+            SrtField::Signal => data.signal as f32,
+            SrtField::Latency => data.latency as f32,
+            SrtField::Bitrate => data.bitrate_mbps,
+            SrtField::Distance => options.unit_system.distance(data.distance),
+        }
+    }
+
+    pub fn is_shown(&self, options: &SrtOptions) -> bool {
+        match self {
+            SrtField::Time => options.show_time,
+            SrtField::SkyBat => options.show_sbat,
+            SrtField::GroundBat => options.show_gbat,
+            SrtField::Signal => options.show_signal,
+            SrtField::Latency => options.show_latency,
+            SrtField::Bitrate => options.show_bitrate,
+            SrtField::Distance => options.show_distance,
+        }
+    }
+
+    pub fn format(&self, data: &SrtFrameData, options: &SrtOptions) -> String {
+        let decimals = options.decimal_places;
+        match self {
+            SrtField::Time => {
+                let minutes = data.flight_time / 60;
+                let seconds = data.flight_time % 60;
+                format!("Time:{}:{:0>2}", minutes, seconds)
+            }
+            SrtField::SkyBat => format!(
+                "SBat:{}",
+                format_voltage(data.sky_bat, options.sky_bat_cells(), decimals)
+            ),
+            SrtField::GroundBat => format!("GBat:{}", format_voltage(data.ground_bat, None, decimals)),
+            SrtField::Signal => format!("Signal:{}", data.signal),
+            SrtField::Latency => format!("Latency:{: >3}ms", data.latency),
+            SrtField::Bitrate => format!("Bitrate:{: >w$.*}Mbps", decimals, data.bitrate_mbps, w = decimals + 3),
+            SrtField::Distance => format!(
+                "Distance:{}",
+                options.unit_system.format_distance(data.distance, decimals)
+            ),
+        }
+    }
+}
+
+impl Display for SrtField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                SrtField::Time => "Time",
+                SrtField::SkyBat => "SBat",
+                SrtField::GroundBat => "GBat",
+                SrtField::Signal => "Signal",
+                SrtField::Latency => "Latency",
+                SrtField::Bitrate => "Bitrate",
+                SrtField::Distance => "Distance",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Comparison {
+    LessThan,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    GreaterThan,
+}
+
+impl Comparison {
+    pub const ALL: [Comparison; 5] = [
+        Comparison::LessThan,
+        Comparison::LessOrEqual,
+        Comparison::Equal,
+        Comparison::GreaterOrEqual,
+        Comparison::GreaterThan,
+    ];
+
+    pub fn matches(&self, value: f32, threshold: f32) -> bool {
+        match self {
+            Comparison::LessThan => value < threshold,
+            Comparison::LessOrEqual => value <= threshold,
+            Comparison::Equal => (value - threshold).abs() < f32::EPSILON,
+            Comparison::GreaterOrEqual => value >= threshold,
+            Comparison::GreaterThan => value > threshold,
+        }
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Comparison::LessThan => "<",
+                Comparison::LessOrEqual => "≤",
+                Comparison::Equal => "=",
+                Comparison::GreaterOrEqual => "≥",
+                Comparison::GreaterThan => ">",
+            }
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Visibility {
+    #[default]
+    Unchanged,
+    Show,
+    Hide,
+}
+
+impl Display for Visibility {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Visibility::Unchanged => "Unchanged",
+                Visibility::Show => "Show",
+                Visibility::Hide => "Hide",
+            }
+        )
+    }
+}
+
+/// Changes the style of a SRT field while its value matches the condition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StyleRule {
+    pub enabled: bool,
+    pub field: SrtField,
+    pub comparison: Comparison,
+    pub threshold: f32,
+    pub change_color: bool,
+    pub color: [f32; 3],
+    pub blink: bool,
+    pub visibility: Visibility,
+}
+
+impl Default for StyleRule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            field: SrtField::Latency,
+            comparison: Comparison::GreaterThan,
+            threshold: 60.0,
+            change_color: true,
+            color: [1.0, 0.0, 0.0],
+            blink: false,
+            visibility: Visibility::Unchanged,
+        }
+    }
+}
+
+/// Style of a single SRT field after applying the rules.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldStyle {
+    pub visible: bool,
+    pub color: [f32; 3],
+    pub blink: bool,
+}
+
+/// Evaluates the rules for the field against the frame data. Rules are applied in order so later rules take precedence.
+pub fn field_style(field: SrtField, data: &SrtFrameData, options: &SrtOptions) -> FieldStyle {
+    let value = field.value(data, options);
+    options
+        .rules
+        .iter()
+        .filter(|rule| rule.enabled && rule.field == field && rule.comparison.matches(value, rule.threshold))
+        .fold(
+            FieldStyle {
+                visible: field.is_shown(options),
+                color: options.text_color,
+                blink: false,
+            },
+            |mut style, rule| {
+                if rule.change_color {
+                    style.color = rule.color;
+                }
+                style.blink |= rule.blink;
+                match rule.visibility {
+                    Visibility::Unchanged => {}
+                    Visibility::Show => style.visible = true,
+                    Visibility::Hide => style.visible = false,
+                }
+                style
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame_data() -> SrtFrameData {
+        SrtFrameData {
+            signal: 1,
+            channel: 8,
+            flight_time: 65,
+            sky_bat: 13.6,
+            ground_bat: 7.2,
+            latency: 72,
+            bitrate_mbps: 25.0,
+            distance: 350,
+        }
+    }
+
+    #[test]
+    fn unmatched_rule_keeps_default_style() {
+        let options = SrtOptions {
+            rules: vec![StyleRule::default()],
+            ..Default::default()
+        };
+        let data = SrtFrameData {
+            latency: 30,
+            ..frame_data()
+        };
+        let style = field_style(SrtField::Latency, &data, &options);
+        assert_eq!(
+            style,
+            FieldStyle {
+                visible: true,
+                color: options.text_color,
+                blink: false
+            }
+        );
+    }
+
+    #[test]
+    fn latency_turns_red_above_threshold() {
+        let options = SrtOptions {
+            rules: vec![StyleRule::default()],
+            ..Default::default()
+        };
+        let style = field_style(SrtField::Latency, &frame_data(), &options);
+        assert_eq!(style.color, [1.0, 0.0, 0.0]);
+        assert!(style.visible);
+
+        let other_field = field_style(SrtField::Bitrate, &frame_data(), &options);
+        assert_eq!(other_field.color, options.text_color);
+    }
+
+    #[test]
+    fn battery_blinks_below_cell_voltage() {
+        let options = SrtOptions {
+            show_cell_voltage: true,
+            cell_count: 4,
+            rules: vec![StyleRule {
+                field: SrtField::SkyBat,
+                comparison: Comparison::LessThan,
+                threshold: 3.5,
+                change_color: false,
+                blink: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let style = field_style(SrtField::SkyBat, &frame_data(), &options);
+        assert!(style.blink);
+        assert_eq!(style.color, options.text_color);
+    }
+
+    #[test]
+    fn later_rules_take_precedence() {
+        let options = SrtOptions {
+            show_signal: false,
+            rules: vec![
+                StyleRule {
+                    field: SrtField::Signal,
+                    comparison: Comparison::LessOrEqual,
+                    threshold: 1.0,
+                    color: [1.0, 1.0, 0.0],
+                    visibility: Visibility::Show,
+                    ..Default::default()
+                },
+                StyleRule {
+                    field: SrtField::Signal,
+                    comparison: Comparison::Equal,
+                    threshold: 1.0,
+                    color: [1.0, 0.5, 0.0],
+                    ..Default::default()
+                },
+                StyleRule {
+                    enabled: false,
+                    field: SrtField::Signal,
+                    comparison: Comparison::Equal,
+                    threshold: 1.0,
+                    visibility: Visibility::Hide,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let style = field_style(SrtField::Signal, &frame_data(), &options);
+        assert!(style.visible);
+        assert_eq!(style.color, [1.0, 0.5, 0.0]);
+    }
+}
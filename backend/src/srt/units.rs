@@ -0,0 +1,92 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+const FEET_PER_METER: f32 = 3.28084;
+const FEET_PER_MILE: f32 = 5280.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnitSystem {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+impl UnitSystem {
+    /// Converts a distance in meters to the base distance unit of the unit system.
+    pub fn distance(&self, meters: u32) -> f32 {
+        match self {
+            UnitSystem::Metric => meters as f32,
+            UnitSystem::Imperial => meters as f32 * FEET_PER_METER,
+        }
+    }
+
+    /// Formats a distance in meters as meters/kilometers or feet/miles. Whole units are used for short distances and
+    /// `decimals + 1` decimal places for long distances.
+    pub fn format_distance(&self, meters: u32, decimals: usize) -> String {
+        match self {
+            UnitSystem::Metric => {
+                if meters > 999 {
+                    format!("{:.*}km", decimals + 1, meters as f32 / 1000.0)
+                } else {
+                    format!("{: >3}m", meters)
+                }
+            }
+            UnitSystem::Imperial => {
+                let feet = self.distance(meters);
+                if feet >= FEET_PER_MILE {
+                    format!("{:.*}mi", decimals + 1, feet / FEET_PER_MILE)
+                } else {
+                    format!("{: >4}ft", feet.round() as u32)
+                }
+            }
+        }
+    }
+}
+
+impl Display for UnitSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UnitSystem::Metric => write!(f, "Metric"),
+            UnitSystem::Imperial => write!(f, "Imperial"),
+        }
+    }
+}
+
+/// Formats a battery voltage, divided over `cell_count` cells when given.
+pub fn format_voltage(volts: f32, cell_count: Option<u8>, decimals: usize) -> String {
+    match cell_count {
+        Some(cells) if cells > 0 => format!("{: >w$.*}V/cell", decimals, volts / cells as f32, w = decimals + 2),
+        _ => format!("{: >w$.*}V", decimals, volts, w = decimals + 3),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_metric_distances() {
+        assert_eq!(UnitSystem::Metric.format_distance(7, 1), "  7m");
+        assert_eq!(UnitSystem::Metric.format_distance(999, 1), "999m");
+        assert_eq!(UnitSystem::Metric.format_distance(1234, 1), "1.23km");
+        assert_eq!(UnitSystem::Metric.format_distance(1234, 0), "1.2km");
+    }
+
+    #[test]
+    fn format_imperial_distances() {
+        assert_eq!(UnitSystem::Imperial.format_distance(10, 1), "  33ft");
+        assert_eq!(UnitSystem::Imperial.format_distance(1000, 1), "3281ft");
+        assert_eq!(UnitSystem::Imperial.format_distance(1610, 1), "1.00mi");
+        assert_eq!(UnitSystem::Imperial.format_distance(3219, 2), "2.000mi");
+    }
+
+    #[test]
+    fn format_voltages() {
+        assert_eq!(format_voltage(16.7, None, 1), "16.7V");
+        assert_eq!(format_voltage(4.7, None, 1), " 4.7V");
+        assert_eq!(format_voltage(16.7, None, 2), "16.70V");
+        assert_eq!(format_voltage(16.8, Some(4), 2), "4.20V/cell");
+        assert_eq!(format_voltage(16.8, Some(0), 1), "16.8V");
+    }
+}
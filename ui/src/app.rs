@@ -6,9 +6,10 @@ use std::{
 use backend::{
     config::AppConfig,
     ffmpeg::{Encoder, FromFfmpegMessage, RenderSettings, ToFfmpegMessage, VideoInfo},
-    font::{self, FontFile},
+    font::{CharacterSize, FontFile, FontLibrary},
     osd::{OsdFile, OsdOptions},
-    srt::{SrtFile, SrtOptions},
+    overlay::{osd_frame_index, srt_frame_index},
+    srt::{SrtFile, SrtFont, SrtOptions},
 };
 use crossbeam_channel::{Receiver, Sender};
 use derivative::Derivative;
@@ -19,8 +20,10 @@ use github_release_check::{GitHubReleaseItem, LookupError};
 use poll_promise::Promise;
 
 use crate::{
+    font_atlas_window::FontAtlas,
     osd_preview::create_osd_preview,
     render_status::RenderStatus,
+    static_layers::StaticLayers,
     util::{set_custom_fonts, set_style},
 };
 
@@ -31,7 +34,16 @@ pub struct WalksnailOsdTool {
     pub video_info: Option<VideoInfo>,
     pub osd_file: Option<OsdFile>,
     pub font_file: Option<FontFile>,
+    pub font_library: FontLibrary,
+    pub font_library_window_open: bool,
+    pub glyph_override_error: Option<String>,
+    pub font_atlas_window_open: bool,
+    pub font_atlas: Option<FontAtlas>,
+    pub new_glyph_override_index: u32,
+    pub save_font_size: CharacterSize,
+    pub save_font_error: Option<String>,
     pub srt_file: Option<SrtFile>,
+    pub srt_font_error: Option<String>,
     pub ui_dimensions: UiDimensions,
     pub to_ffmpeg_sender: Option<Sender<ToFfmpegMessage>>,
     pub from_ffmpeg_receiver: Option<Receiver<FromFfmpegMessage>>,
@@ -42,7 +54,8 @@ pub struct WalksnailOsdTool {
     pub osd_preview: OsdPreview,
     pub osd_options: OsdOptions,
     pub srt_options: SrtOptions,
-    pub srt_font: Option<rusttype::Font<'static>>,
+    pub srt_font: SrtFont,
+    pub static_layers: StaticLayers,
     pub about_window_open: bool,
     pub dark_mode: bool,
     pub app_update: AppUpdate,
@@ -68,37 +81,52 @@ impl WalksnailOsdTool {
         set_custom_fonts(ctx);
         ctx.set_visuals(visuals);
 
-        let srt_font: rusttype::Font<'static> =
-            rusttype::Font::try_from_bytes(include_bytes!("../../resources/fonts/AzeretMono-Regular.ttf")).unwrap();
-
         let srt_options = saved_settings.srt_options;
         let osd_options = saved_settings.osd_options;
 
         // Load last used font file
         let font_path = PathBuf::from(saved_settings.font_path);
-        let font_file = font::FontFile::open(font_path).ok();
+        let font_file = FontFile::open(font_path).ok();
+
+        let mut font_library = saved_settings.font_library;
+        font_library.scan().ok();
+
+        // Load last used SRT font, the bundled font is used when none was selected
+        let (srt_font, srt_font_error) = if saved_settings.srt_font_path.is_empty() {
+            (SrtFont::bundled(), None)
+        } else {
+            match SrtFont::open(PathBuf::from(saved_settings.srt_font_path)) {
+                Ok(srt_font) => (srt_font, None),
+                Err(e) => (SrtFont::default(), Some(e.to_string())),
+            }
+        };
 
         let app_update = AppUpdate {
             promise: update_check_promise,
             ..Default::default()
         };
 
-        Self {
+        let mut app = Self {
             dependencies: Dependencies {
                 dependencies_satisfied,
                 ffmpeg_path,
                 ffprobe_path,
             },
             encoders,
-            srt_font: Some(srt_font),
+            srt_font,
+            srt_font_error,
             osd_options,
             srt_options,
             font_file,
+            font_library,
             app_update,
             app_version,
             target,
             ..Default::default()
-        }
+        };
+        app.apply_glyph_overrides();
+        app.update_static_layers(false);
+        app
     }
 }
 
@@ -113,8 +141,8 @@ pub struct Dependencies {
 #[derivative(Default)]
 pub struct OsdPreview {
     pub texture_handle: Option<TextureHandle>,
-    #[derivative(Default(value = "1"))]
-    pub preview_frame: u32,
+    /// Video time that is shown in the preview.
+    pub preview_time_secs: f32,
     pub mask_edit_mode_enabled: bool,
 }
 
@@ -183,6 +211,10 @@ impl eframe::App for WalksnailOsdTool {
 
         self.render_central_panel(ctx);
 
+        self.render_font_library_window(ctx);
+
+        self.render_font_atlas_window(ctx);
+
         self.save_config_if_changed();
     }
 }
@@ -216,24 +248,30 @@ impl WalksnailOsdTool {
     }
 
     pub fn update_osd_preview(&mut self, ctx: &egui::Context) {
-        if let (Some(video_info), Some(osd_file), Some(font_file), Some(srt_file)) =
-            (&self.video_info, &self.osd_file, &self.font_file, &self.srt_file)
-        {
+        self.update_static_layers(false);
+        if let Some(video_info) = &self.video_info {
+            // The preview layers only contain the frames that are shown at the preview time, which are looked up the
+            // same way as during a render
+            let preview_time_secs = self.osd_preview.preview_time_secs;
+            self.osd_options.osd_playback_speed_factor = self.osd_playback_speed_factor();
+            let speed_factor = self.osd_options.osd_playback_speed_factor;
+            let layers = self.overlay_layers(
+                |osd_file| {
+                    osd_frame_index(&osd_file.frames, speed_factor, preview_time_secs)
+                        .map(|index| osd_file.frames[index].clone())
+                        .into_iter()
+                        .collect()
+                },
+                |srt_file| {
+                    srt_frame_index(&srt_file.frames, preview_time_secs)
+                        .map(|index| srt_file.frames[index].clone())
+                        .into_iter()
+                        .collect()
+                },
+            );
             let image = egui::ColorImage::from_rgba_unmultiplied(
                 [video_info.width as usize, video_info.height as usize],
-                &create_osd_preview(
-                    video_info.width,
-                    video_info.height,
-                    osd_file
-                        .frames
-                        .get(self.osd_preview.preview_frame as usize - 1)
-                        .unwrap(),
-                    srt_file.frames.last().unwrap(),
-                    font_file,
-                    self.srt_font.as_ref().unwrap(),
-                    &self.osd_options,
-                    &self.srt_options,
-                ),
+                &create_osd_preview(video_info.width, video_info.height, &layers, preview_time_secs),
             );
             let handle = ctx.load_texture("OSD preview", image, egui::TextureOptions::default());
             self.osd_preview.texture_handle = Some(handle);
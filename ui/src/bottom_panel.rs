@@ -20,39 +20,27 @@ impl WalksnailOsdTool {
         let button_size = vec2(110.0, 40.0);
         if self.render_status.is_not_in_progress() {
             if ui
-                .add_enabled(
-                    self.all_files_loaded(),
-                    Button::new("Start render").min_size(button_size),
-                )
-                .on_disabled_hover_text("First load video, OSD, SRT and font files")
+                .add_enabled(self.video_loaded(), Button::new("Start render").min_size(button_size))
+                .on_hover_text(self.render_layers_description())
+                .on_disabled_hover_text("First load a video file")
                 .clicked()
             {
                 tracing::info!("Start render button clicked");
                 self.render_status.start_render();
-                if let (Some(video_path), Some(osd_file), Some(font_file), Some(video_info), Some(srt_file)) = (
-                    &self.video_file,
-                    &self.osd_file,
-                    &self.font_file,
-                    &self.video_info,
-                    &self.srt_file,
-                ) {
-                    self.osd_options.osd_playback_speed_factor = if self.osd_options.adjust_playback_speed {
-                        let video_duration = video_info.duration;
-                        let osd_duration = osd_file.duration;
-                        video_duration.as_secs_f32() / osd_duration.as_secs_f32()
-                    } else {
-                        1.0
+                self.update_static_layers(true);
+                if let Some(error) = self.static_layers_error() {
+                    tracing::error!("Not starting render, a layer can't be opened: {}", error);
+                    self.render_status.status = Status::Error {
+                        progress_pct: 0.0,
+                        error,
                     };
+                } else if let (Some(video_path), Some(video_info)) = (&self.video_file, &self.video_info) {
+                    self.osd_options.osd_playback_speed_factor = self.osd_playback_speed_factor();
                     match start_video_render(
                         &self.dependencies.ffmpeg_path,
                         video_path,
-                        &get_output_video_path(video_path),
-                        osd_file.frames.clone(),
-                        srt_file.frames.clone(),
-                        font_file.clone(),
-                        self.srt_font.as_ref().unwrap().clone(),
-                        &self.osd_options,
-                        &self.srt_options,
+                        &get_output_video_path(video_path, self.render_settings.transparent_format()),
+                        self.overlay_layers(|f| f.frames.clone(), |f| f.frames.clone()),
                         video_info,
                         &self.render_settings,
                     ) {
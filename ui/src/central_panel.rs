@@ -1,9 +1,16 @@
 use std::time::Instant;
 
-use backend::util::Coordinates;
+use backend::{
+    ffmpeg::{RenderMode, TransparentFormat},
+    font::{CharacterSize, FontFile, FontType, ScalingFilter},
+    osd::{OsdScalingMode, GRID_HEIGHT, GRID_WIDTH},
+    overlay::{get_character_size, grid_offset, BlendMode, ImageLayerOptions, LayerKind, TextLayerOptions},
+    srt::{Comparison, SrtField, SrtFont, StyleRule, UnitSystem, Visibility},
+    util::Coordinates,
+};
 use egui::{
-    vec2, Button, CentralPanel, Checkbox, CollapsingHeader, Color32, CursorIcon, Grid, Image, Rect, RichText,
-    ScrollArea, Sense, Slider, Stroke, Ui, Vec2,
+    vec2, Button, CentralPanel, Checkbox, CollapsingHeader, Color32, CursorIcon, DragValue, Grid, Image, Rect,
+    RichText, ScrollArea, Sense, Slider, Stroke, TextEdit, Ui, Vec2,
 };
 
 use crate::{
@@ -26,17 +33,22 @@ impl WalksnailOsdTool {
 
                 separator_with_space(ui, 10.0);
 
+                self.image_and_text_layers(ui, ctx);
+
+                separator_with_space(ui, 10.0);
+
                 self.osd_preview(ui, ctx);
 
                 separator_with_space(ui, 10.0);
 
-                self.rendering_options(ui);
+                self.rendering_options(ui, ctx);
             });
         });
     }
 
     fn osd_options(&mut self, ui: &mut Ui, ctx: &egui::Context) {
         let mut changed = false;
+        let mut overrides_changed = false;
 
         CollapsingHeader::new(RichText::new("OSD Options").heading())
             .default_open(true)
@@ -45,24 +57,18 @@ impl WalksnailOsdTool {
                     .min_col_width(self.ui_dimensions.options_column1_width)
                     .show(ui, |ui| {
                         ui.label("Horizontal position")
-                            .on_hover_text(tooltip_text("Horizontal position of the flight controller OSD (pixels from the left edge of the video)."));
+                            .on_hover_text(tooltip_text("Horizontal position of the flight controller OSD (pixels from the centered position)."));
                         ui.horizontal(|ui| {
                             changed |= ui
                                 .add(Slider::new(&mut self.osd_options.position.x, -200..=700).text("Pixels"))
                                 .changed();
 
                             if ui.button("Center").clicked() {
-                                if let (Some(video_info), Some(osd_file), Some(font_file)) =
-                                    (&self.video_info, &self.osd_file, &self.font_file)
-                                {
-                                    self.osd_options.position.x = calculate_horizontal_offset(
-                                        video_info.width,
-                                        osd_file
-                                            .frames
-                                            .get(self.osd_preview.preview_frame as usize - 1)
-                                            .unwrap(),
-                                        &font_file.character_size,
-                                    );
+                                if let (Some(video_info), Some(osd_frame)) = (&self.video_info, self.preview_osd_frame()) {
+                                    let character_size =
+                                        get_character_size(video_info.width, video_info.height, &self.osd_options);
+                                    self.osd_options.position.x =
+                                        calculate_horizontal_offset(video_info.width, osd_frame, &character_size);
                                     changed |= true;
                                 }
                             }
@@ -77,24 +83,18 @@ impl WalksnailOsdTool {
                         //
 
                         ui.label("Vertical position")
-                            .on_hover_text(tooltip_text("Vertical position of the flight controller OSD (pixels from the top of the video).").small());
+                            .on_hover_text(tooltip_text("Vertical position of the flight controller OSD (pixels from the centered position).").small());
                         ui.horizontal(|ui| {
                             changed |= ui
                                 .add(Slider::new(&mut self.osd_options.position.y, -200..=700).text("Pixels"))
                                 .changed();
 
                             if ui.button("Center").clicked() {
-                                if let (Some(video_info), Some(osd_file), Some(font_file)) =
-                                    (&self.video_info, &self.osd_file, &self.font_file)
-                                {
-                                    self.osd_options.position.y = calculate_vertical_offset(
-                                        video_info.height,
-                                        osd_file
-                                            .frames
-                                            .get(self.osd_preview.preview_frame as usize - 1)
-                                            .unwrap(),
-                                        &font_file.character_size,
-                                    );
+                                if let (Some(video_info), Some(osd_frame)) = (&self.video_info, self.preview_osd_frame()) {
+                                    let character_size =
+                                        get_character_size(video_info.width, video_info.height, &self.osd_options);
+                                    self.osd_options.position.y =
+                                        calculate_vertical_offset(video_info.height, osd_frame, &character_size);
                                     changed |= true
                                 }
                             }
@@ -106,12 +106,223 @@ impl WalksnailOsdTool {
                         });
                         ui.end_row();
 
+                        ui.label("Scaling")
+                            .on_hover_text(tooltip_text("How the OSD grid is scaled to the video. Fit keeps the whole grid visible, fill covers the video and stretch ignores the aspect ratio of the characters."));
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_source("osd_scaling_mode")
+                                .selected_text(self.osd_options.scaling_mode.to_string())
+                                .show_ui(ui, |ui| {
+                                    for scaling_mode in OsdScalingMode::ALL {
+                                        changed |= ui
+                                            .selectable_value(&mut self.osd_options.scaling_mode, scaling_mode, scaling_mode.to_string())
+                                            .changed();
+                                    }
+                                });
+                            changed |= ui
+                                .add(Slider::new(&mut self.osd_options.scale, 0.5..=2.0).fixed_decimals(2).text("Scale"))
+                                .changed();
+                            if ui.button("Reset").clicked() {
+                                self.osd_options.scale = 1.0;
+                                changed |= true;
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Font library")
+                            .on_hover_text(tooltip_text("Directory of fonts from which the font matching the flight controller firmware and video resolution is loaded when an OSD file is opened."));
+                        ui.horizontal(|ui| {
+                            if ui.button("Edit").clicked() {
+                                self.font_library_window_open = !self.font_library_window_open;
+                            }
+                            ui.label(format!("{} fonts", self.font_library.entries().len()));
+                        });
+                        ui.end_row();
+
+                        ui.label("Color page")
+                            .on_hover_text(tooltip_text("Column of a four color font that is used for the OSD."));
+                        ui.horizontal(|ui| {
+                            let four_color_font = self.font_file.as_ref().is_some_and(|f| f.font_type == FontType::FourColor);
+                            ui.add_enabled_ui(four_color_font, |ui| {
+                                for color_page in 0..FontType::FourColor.pages() {
+                                    if ui
+                                        .selectable_value(&mut self.osd_options.color_page, color_page, (color_page + 1).to_string())
+                                        .changed()
+                                    {
+                                        // The font browser checks the glyphs of the selected color page
+                                        self.font_atlas = None;
+                                        changed |= true;
+                                    }
+                                }
+                            })
+                            .response
+                            .on_disabled_hover_text(tooltip_text("Only available for four color fonts"));
+                        });
+                        ui.end_row();
+
+                        ui.label("Font")
+                            .on_hover_text(tooltip_text("Show all characters of the font and check the glyphs used by the OSD file for problems."));
+                        ui.horizontal(|ui| {
+                            if ui.add_enabled(self.font_loaded(), Button::new("Browse")).clicked() {
+                                self.font_atlas_window_open = !self.font_atlas_window_open;
+                            }
+                            if ui
+                                .button("Use bundled text font")
+                                .on_hover_text(tooltip_text("Letters, digits and punctuation only. Firmware symbols like the battery and signal icons are not drawn."))
+                                .clicked()
+                            {
+                                self.set_font_file(FontFile::bundled_text());
+                                changed |= true;
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Glyph overrides")
+                            .on_hover_text(tooltip_text("Images that replace characters of the font. The index is the position of the character in the font, counting down the pages from left to right."));
+                        ui.vertical(|ui| {
+                            let mut removed = None;
+                            for (index, path) in &self.osd_options.glyph_overrides {
+                                ui.horizontal(|ui| {
+                                    let file_name = path.file_name().map(|f| f.to_string_lossy()).unwrap_or_default();
+                                    ui.label(format!("{index}: {file_name}"));
+                                    if ui.button("Remove").clicked() {
+                                        removed = Some(*index);
+                                    }
+                                });
+                            }
+                            if let Some(index) = removed {
+                                self.osd_options.glyph_overrides.remove(&index);
+                                overrides_changed = true;
+                            }
+
+                            ui.horizontal(|ui| {
+                                ui.add(DragValue::new(&mut self.new_glyph_override_index).range(0..=2047).prefix("Index "));
+                                if ui.button("Add image").clicked() {
+                                    if let Some(path) = rfd::FileDialog::new().add_filter("PNG image", &["png"]).pick_file() {
+                                        self.osd_options.glyph_overrides.insert(self.new_glyph_override_index, path);
+                                        overrides_changed = true;
+                                    }
+                                }
+                            });
+
+                            if let Some(error) = &self.glyph_override_error {
+                                ui.label(RichText::new(error).color(Color32::RED));
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Save font")
+                            .on_hover_text(tooltip_text("Save the loaded font including the glyph overrides as a PNG font in the selected size."));
+                        ui.vertical(|ui| {
+                            ui.horizontal(|ui| {
+                                egui::ComboBox::from_id_source("save_font_size")
+                                    .selected_text(self.save_font_size.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for size in CharacterSize::STANDARD {
+                                            let text = size.to_string();
+                                            ui.selectable_value(&mut self.save_font_size, size, text);
+                                        }
+                                    });
+                                if ui.add_enabled(self.font_loaded(), Button::new("Save as PNG")).clicked() {
+                                    if let (Some(font_file), Some(path)) = (
+                                        &self.font_file,
+                                        rfd::FileDialog::new().add_filter("PNG image", &["png"]).save_file(),
+                                    ) {
+                                        self.save_font_error = font_file
+                                            .save_png(&path, &self.save_font_size, self.render_settings.glyph_scaling_filter)
+                                            .err()
+                                            .map(|e| e.to_string());
+                                    }
+                                }
+                            });
+
+                            if let Some(error) = &self.save_font_error {
+                                ui.label(RichText::new(error).color(Color32::RED));
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Outline").on_hover_text(tooltip_text(
+                            "Draw an outline around the OSD characters to make them readable on bright backgrounds.",
+                        ));
+                        ui.horizontal(|ui| {
+                            changed |= ui
+                                .add(Checkbox::without_text(&mut self.osd_options.show_outline))
+                                .changed();
+                            ui.add_enabled_ui(self.osd_options.show_outline, |ui| {
+                                changed |= ui.color_edit_button_rgb(&mut self.osd_options.outline_color).changed();
+                                changed |= ui
+                                    .add(
+                                        Slider::new(&mut self.osd_options.outline_width, 1.0..=10.0)
+                                            .text("Width")
+                                            .fixed_decimals(0),
+                                    )
+                                    .changed();
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label("Shadow")
+                            .on_hover_text(tooltip_text("Draw a drop shadow behind the OSD characters."));
+                        ui.horizontal(|ui| {
+                            changed |= ui
+                                .add(Checkbox::without_text(&mut self.osd_options.show_shadow))
+                                .changed();
+                            ui.add_enabled_ui(self.osd_options.show_shadow, |ui| {
+                                changed |= ui.color_edit_button_rgb(&mut self.osd_options.shadow_color).changed();
+                                changed |= ui
+                                    .add(
+                                        Slider::new(&mut self.osd_options.shadow_opacity, 0.0..=1.0)
+                                            .text("Opacity")
+                                            .fixed_decimals(2),
+                                    )
+                                    .changed();
+                                changed |= ui
+                                    .add(
+                                        Slider::new(&mut self.osd_options.shadow_blur, 0.0..=10.0)
+                                            .text("Blur")
+                                            .fixed_decimals(1),
+                                    )
+                                    .changed();
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label("Shadow offset").on_hover_text(tooltip_text(
+                            "Horizontal and vertical offset of the drop shadow (pixels at 1080p).",
+                        ));
+                        ui.add_enabled_ui(self.osd_options.show_shadow, |ui| {
+                            ui.horizontal(|ui| {
+                                changed |= ui
+                                    .add(
+                                        Slider::new(&mut self.osd_options.shadow_offset.x, -10.0..=10.0)
+                                            .text("X")
+                                            .fixed_decimals(0),
+                                    )
+                                    .changed();
+                                changed |= ui
+                                    .add(
+                                        Slider::new(&mut self.osd_options.shadow_offset.y, -10.0..=10.0)
+                                            .text("Y")
+                                            .fixed_decimals(0),
+                                    )
+                                    .changed();
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label("Opacity")
+                            .on_hover_text(tooltip_text("Opacity of the OSD, including the outline and shadow."));
+                        changed |= ui
+                            .add(Slider::new(&mut self.osd_options.opacity, 0.0..=1.0).fixed_decimals(2))
+                            .changed();
+                        ui.end_row();
+
                         ui.label("Mask")
                             .on_hover_text(tooltip_text("Click edit to select OSD elements on the preview that should not be rendered on the video. This can be useful to hide GPS coordinates, etc."));
                         ui.horizontal(|ui| {
-                            let txt = if !self.osd_preview.mask_edit_mode_enabled || !self.all_files_loaded() {"Edit"} else {"Save"};
-                            if ui.add_enabled(self.all_files_loaded(), Button::new(txt))
-                                .on_disabled_hover_text(tooltip_text("First load the input files")).clicked() {
+                            let txt = if !self.osd_preview.mask_edit_mode_enabled || !self.osd_preview_loaded() {"Edit"} else {"Save"};
+                            if ui.add_enabled(self.osd_preview_loaded(), Button::new(txt))
+                                .on_disabled_hover_text(tooltip_text("First load the video, OSD and font files")).clicked() {
                                 self.osd_preview.mask_edit_mode_enabled = !self.osd_preview.mask_edit_mode_enabled;
                             }
                             if ui.button("Reset").clicked() {
@@ -134,6 +345,11 @@ impl WalksnailOsdTool {
                     });
             });
 
+        if overrides_changed {
+            self.apply_glyph_overrides();
+            changed = true;
+        }
+
         if changed {
             self.update_osd_preview(ctx);
             self.config_changed = Some(Instant::now());
@@ -193,6 +409,34 @@ impl WalksnailOsdTool {
                         });
                         ui.end_row();
 
+                        ui.label("Font").on_hover_text(tooltip_text(
+                            "TrueType or OpenType font for the SRT data. Missing characters are taken from the next font.",
+                        ));
+                        ui.horizontal(|ui| {
+                            ui.label(self.srt_font.fallback_chain().join(" → "));
+
+                            if ui.button("Select").clicked() {
+                                if let Some(font_path) = rfd::FileDialog::new()
+                                    .add_filter("Font files", &["ttf", "otf"])
+                                    .pick_file()
+                                {
+                                    self.import_srt_font_file(&[font_path]);
+                                    changed |= true;
+                                }
+                            }
+
+                            if ui.button("Reset").clicked() {
+                                self.srt_font = SrtFont::bundled();
+                                self.srt_font_error = None;
+                                changed |= true;
+                            }
+
+                            if let Some(error) = &self.srt_font_error {
+                                ui.label(RichText::new(error).color(Color32::RED));
+                            }
+                        });
+                        ui.end_row();
+
                         ui.label("SRT data").on_hover_text(tooltip_text(
                             "Select data from the SRT file to be rendered on the video.",
                         ));
@@ -213,6 +457,256 @@ impl WalksnailOsdTool {
                             ui.end_row();
                         });
                         ui.end_row();
+
+                        ui.label("Units").on_hover_text(tooltip_text(
+                            "Unit system for the distance and number of decimal places for voltages, bitrate and long distances.",
+                        ));
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_source("unit_system")
+                                .selected_text(self.srt_options.unit_system.to_string())
+                                .show_ui(ui, |ui| {
+                                    for unit_system in [UnitSystem::Metric, UnitSystem::Imperial] {
+                                        changed |= ui
+                                            .selectable_value(
+                                                &mut self.srt_options.unit_system,
+                                                unit_system,
+                                                unit_system.to_string(),
+                                            )
+                                            .changed();
+                                    }
+                                });
+                            changed |= ui
+                                .add(Slider::new(&mut self.srt_options.decimal_places, 0..=3).text("Decimals"))
+                                .changed();
+                        });
+                        ui.end_row();
+
+                        ui.label("Cell voltage").on_hover_text(tooltip_text(
+                            "Show the sky battery voltage per cell instead of the total pack voltage.",
+                        ));
+                        ui.horizontal(|ui| {
+                            changed |= ui
+                                .add(Checkbox::without_text(&mut self.srt_options.show_cell_voltage))
+                                .changed();
+                            ui.add_enabled_ui(self.srt_options.show_cell_voltage, |ui| {
+                                changed |= ui
+                                    .add(Slider::new(&mut self.srt_options.cell_count, 1..=12).text("Cells"))
+                                    .changed();
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label("Text color")
+                            .on_hover_text(tooltip_text("Color and opacity of the SRT text."));
+                        ui.horizontal(|ui| {
+                            changed |= ui.color_edit_button_rgb(&mut self.srt_options.text_color).changed();
+                            changed |= ui
+                                .add(
+                                    Slider::new(&mut self.srt_options.text_opacity, 0.0..=1.0)
+                                        .text("Opacity")
+                                        .fixed_decimals(2),
+                                )
+                                .changed();
+                        });
+                        ui.end_row();
+
+                        ui.label("Outline").on_hover_text(tooltip_text(
+                            "Draw an outline around the SRT text to make it readable on bright backgrounds.",
+                        ));
+                        ui.horizontal(|ui| {
+                            changed |= ui
+                                .add(Checkbox::without_text(&mut self.srt_options.show_outline))
+                                .changed();
+                            ui.add_enabled_ui(self.srt_options.show_outline, |ui| {
+                                changed |= ui.color_edit_button_rgb(&mut self.srt_options.outline_color).changed();
+                                changed |= ui
+                                    .add(
+                                        Slider::new(&mut self.srt_options.outline_width, 1.0..=10.0)
+                                            .text("Width")
+                                            .fixed_decimals(0),
+                                    )
+                                    .changed();
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label("Shadow")
+                            .on_hover_text(tooltip_text("Draw a drop shadow behind the SRT text."));
+                        ui.horizontal(|ui| {
+                            changed |= ui
+                                .add(Checkbox::without_text(&mut self.srt_options.show_shadow))
+                                .changed();
+                            ui.add_enabled_ui(self.srt_options.show_shadow, |ui| {
+                                changed |= ui.color_edit_button_rgb(&mut self.srt_options.shadow_color).changed();
+                                changed |= ui
+                                    .add(
+                                        Slider::new(&mut self.srt_options.shadow_opacity, 0.0..=1.0)
+                                            .text("Opacity")
+                                            .fixed_decimals(2),
+                                    )
+                                    .changed();
+                                changed |= ui
+                                    .add(
+                                        Slider::new(&mut self.srt_options.shadow_blur, 0.0..=10.0)
+                                            .text("Blur")
+                                            .fixed_decimals(1),
+                                    )
+                                    .changed();
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label("Shadow offset").on_hover_text(tooltip_text(
+                            "Horizontal and vertical offset of the drop shadow (pixels at 1080p).",
+                        ));
+                        ui.add_enabled_ui(self.srt_options.show_shadow, |ui| {
+                            ui.horizontal(|ui| {
+                                changed |= ui
+                                    .add(
+                                        Slider::new(&mut self.srt_options.shadow_offset.x, -10.0..=10.0)
+                                            .text("X")
+                                            .fixed_decimals(0),
+                                    )
+                                    .changed();
+                                changed |= ui
+                                    .add(
+                                        Slider::new(&mut self.srt_options.shadow_offset.y, -10.0..=10.0)
+                                            .text("Y")
+                                            .fixed_decimals(0),
+                                    )
+                                    .changed();
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label("Background").on_hover_text(tooltip_text(
+                            "Draw a semi-transparent box with rounded corners behind the SRT text.",
+                        ));
+                        ui.horizontal(|ui| {
+                            changed |= ui
+                                .add(Checkbox::without_text(&mut self.srt_options.show_background))
+                                .changed();
+                            ui.add_enabled_ui(self.srt_options.show_background, |ui| {
+                                changed |= ui
+                                    .color_edit_button_rgb(&mut self.srt_options.background_color)
+                                    .changed();
+                                changed |= ui
+                                    .add(
+                                        Slider::new(&mut self.srt_options.background_opacity, 0.0..=1.0)
+                                            .text("Opacity")
+                                            .fixed_decimals(2),
+                                    )
+                                    .changed();
+                                changed |= ui
+                                    .add(
+                                        Slider::new(&mut self.srt_options.background_corner_radius, 0.0..=30.0)
+                                            .text("Radius")
+                                            .fixed_decimals(0),
+                                    )
+                                    .changed();
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label("Rules").on_hover_text(tooltip_text(
+                            "Change the style of SRT data when a value crosses a threshold. Later rules take precedence.",
+                        ));
+                        changed |= self.srt_rules(ui);
+                        ui.end_row();
+                    });
+            });
+
+        if changed {
+            self.update_osd_preview(ctx);
+            self.config_changed = Some(Instant::now());
+        }
+    }
+
+    fn srt_rules(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        let mut remove_idx = None;
+
+        ui.vertical(|ui| {
+            for (idx, rule) in self.srt_options.rules.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    changed |= ui.add(Checkbox::without_text(&mut rule.enabled)).changed();
+
+                    egui::ComboBox::from_id_source(("rule_field", idx))
+                        .width(80.0)
+                        .selected_text(rule.field.to_string())
+                        .show_ui(ui, |ui| {
+                            for field in SrtField::ALL {
+                                changed |= ui.selectable_value(&mut rule.field, field, field.to_string()).changed();
+                            }
+                        });
+
+                    egui::ComboBox::from_id_source(("rule_comparison", idx))
+                        .width(40.0)
+                        .selected_text(rule.comparison.to_string())
+                        .show_ui(ui, |ui| {
+                            for comparison in Comparison::ALL {
+                                changed |= ui
+                                    .selectable_value(&mut rule.comparison, comparison, comparison.to_string())
+                                    .changed();
+                            }
+                        });
+
+                    changed |= ui.add(DragValue::new(&mut rule.threshold).speed(0.1)).changed();
+
+                    changed |= ui.checkbox(&mut rule.change_color, "Color").changed();
+                    ui.add_enabled_ui(rule.change_color, |ui| {
+                        changed |= ui.color_edit_button_rgb(&mut rule.color).changed();
+                    });
+
+                    changed |= ui.checkbox(&mut rule.blink, "Blink").changed();
+
+                    egui::ComboBox::from_id_source(("rule_visibility", idx))
+                        .width(90.0)
+                        .selected_text(rule.visibility.to_string())
+                        .show_ui(ui, |ui| {
+                            for visibility in [Visibility::Unchanged, Visibility::Show, Visibility::Hide] {
+                                changed |= ui
+                                    .selectable_value(&mut rule.visibility, visibility, visibility.to_string())
+                                    .changed();
+                            }
+                        });
+
+                    if ui.button("🗑").clicked() {
+                        remove_idx = Some(idx);
+                    }
+                });
+            }
+
+            if ui.button("Add rule").clicked() {
+                self.srt_options.rules.push(StyleRule::default());
+                changed |= true;
+            }
+        });
+
+        if let Some(idx) = remove_idx {
+            self.srt_options.rules.remove(idx);
+            changed |= true;
+        }
+
+        changed
+    }
+
+    fn image_and_text_layers(&mut self, ui: &mut Ui, ctx: &egui::Context) {
+        let mut changed = false;
+
+        CollapsingHeader::new(RichText::new("Image and Text Layers").heading())
+            .default_open(true)
+            .show_unindented(ui, |ui| {
+                Grid::new("image_and_text_layers")
+                    .min_col_width(self.ui_dimensions.options_column1_width)
+                    .show(ui, |ui| {
+                        ui.label("Images").on_hover_text(tooltip_text("PNG images, like a logo, that are drawn on every frame. The position of the top left corner is in % of the video width and height, the scale is relative to the image size at 1080p."));
+                        changed |= self.image_layer_options(ui);
+                        ui.end_row();
+
+                        ui.label("Text").on_hover_text(tooltip_text("Text, like the pilot name, that is drawn on every frame. The position of the top left corner is in % of the video width and height, the size is in pixels at 1080p."));
+                        changed |= self.text_layer_options(ui);
+                        ui.end_row();
                     });
             });
 
@@ -222,6 +716,172 @@ impl WalksnailOsdTool {
         }
     }
 
+    fn image_layer_options(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        let mut remove_idx = None;
+
+        ui.vertical(|ui| {
+            for (idx, options) in self.render_settings.image_layers.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    let file_name = options
+                        .path
+                        .file_name()
+                        .map(|f| f.to_string_lossy())
+                        .unwrap_or_default();
+                    if let Some(error) = self.static_layers.image_error(idx) {
+                        ui.label(RichText::new(file_name).color(Color32::RED))
+                            .on_hover_text(error);
+                    } else {
+                        ui.label(file_name);
+                    }
+                    if ui.button("Select").clicked() {
+                        if let Some(path) = rfd::FileDialog::new().add_filter("PNG image", &["png"]).pick_file() {
+                            options.path = path;
+                            changed |= true;
+                        }
+                    }
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut options.position.x)
+                                .range(0.0..=100.0)
+                                .speed(0.1)
+                                .prefix("X "),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut options.position.y)
+                                .range(0.0..=100.0)
+                                .speed(0.1)
+                                .prefix("Y "),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            Slider::new(&mut options.scale, 0.1..=4.0)
+                                .fixed_decimals(2)
+                                .text("Scale"),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            Slider::new(&mut options.opacity, 0.0..=1.0)
+                                .fixed_decimals(2)
+                                .text("Opacity"),
+                        )
+                        .changed();
+
+                    if ui.button("🗑").clicked() {
+                        remove_idx = Some(idx);
+                    }
+                });
+            }
+
+            if ui.button("Add image").clicked() {
+                if let Some(path) = rfd::FileDialog::new().add_filter("PNG image", &["png"]).pick_file() {
+                    self.render_settings.image_layers.push(ImageLayerOptions {
+                        path,
+                        ..Default::default()
+                    });
+                    changed |= true;
+                }
+            }
+        });
+
+        if let Some(idx) = remove_idx {
+            self.render_settings.image_layers.remove(idx);
+            changed |= true;
+        }
+
+        changed
+    }
+
+    fn text_layer_options(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        let mut remove_idx = None;
+
+        ui.vertical(|ui| {
+            for (idx, options) in self.render_settings.text_layers.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .add(TextEdit::singleline(&mut options.text).desired_width(120.0))
+                        .changed();
+                    changed |= ui.color_edit_button_rgb(&mut options.color).changed();
+
+                    let font_name = options
+                        .font_path
+                        .as_ref()
+                        .and_then(|path| path.file_name())
+                        .map(|f| f.to_string_lossy().to_string())
+                        .unwrap_or("Bundled font".to_string());
+                    let font_button = match self.static_layers.text_error(idx) {
+                        Some(error) => ui
+                            .button(RichText::new(font_name).color(Color32::RED))
+                            .on_hover_text(error),
+                        None => ui.button(font_name).on_hover_text("Select a TrueType or OpenType font"),
+                    };
+                    if font_button.clicked() {
+                        if let Some(font_path) = rfd::FileDialog::new()
+                            .add_filter("Font files", &["ttf", "otf"])
+                            .pick_file()
+                        {
+                            options.font_path = Some(font_path);
+                            changed |= true;
+                        }
+                    }
+                    if options.font_path.is_some() && ui.button("Reset").clicked() {
+                        options.font_path = None;
+                        changed |= true;
+                    }
+
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut options.position.x)
+                                .range(0.0..=100.0)
+                                .speed(0.1)
+                                .prefix("X "),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(
+                            DragValue::new(&mut options.position.y)
+                                .range(0.0..=100.0)
+                                .speed(0.1)
+                                .prefix("Y "),
+                        )
+                        .changed();
+                    changed |= ui
+                        .add(DragValue::new(&mut options.size).range(10.0..=200.0).prefix("Size "))
+                        .changed();
+
+                    changed |= ui.checkbox(&mut options.show_outline, "Outline").changed();
+                    ui.add_enabled_ui(options.show_outline, |ui| {
+                        changed |= ui.color_edit_button_rgb(&mut options.outline_color).changed();
+                        changed |= ui
+                            .add(DragValue::new(&mut options.outline_width).range(0.0..=10.0).speed(0.1))
+                            .changed();
+                    });
+
+                    if ui.button("🗑").clicked() {
+                        remove_idx = Some(idx);
+                    }
+                });
+            }
+
+            if ui.button("Add text").clicked() {
+                self.render_settings.text_layers.push(TextLayerOptions::default());
+                changed |= true;
+            }
+        });
+
+        if let Some(idx) = remove_idx {
+            self.render_settings.text_layers.remove(idx);
+            changed |= true;
+        }
+
+        changed
+    }
+
     fn osd_preview(&mut self, ui: &mut Ui, ctx: &egui::Context) {
         CollapsingHeader::new(RichText::new("Preview").heading())
             .default_open(true)
@@ -230,6 +890,7 @@ impl WalksnailOsdTool {
                     let preview_width = ui.available_width();
                     let aspect_ratio = video_info.width as f32 / video_info.height as f32;
                     let preview_height = preview_width / aspect_ratio;
+                    let video_duration_secs = video_info.duration.as_secs_f32();
                     let image = Image::new(handle).fit_to_exact_size(Vec2::new(preview_width, preview_height));
                     let rect = ui.add(image.bg_fill(Color32::LIGHT_GRAY)).rect;
 
@@ -238,17 +899,15 @@ impl WalksnailOsdTool {
                     }
 
                     ui.horizontal(|ui| {
-                        ui.label("Preview frame").on_hover_text(tooltip_text(
-                            "The selected frame is also used for centering the OSD under OSD Options.",
+                        ui.label("Preview time").on_hover_text(tooltip_text(
+                            "The OSD shown at the selected time is also used for centering the OSD under OSD Options.",
                         ));
-                        let preview_frame_slider = ui.add(
-                            Slider::new(
-                                &mut self.osd_preview.preview_frame,
-                                1..=self.osd_file.as_ref().map(|f| f.frame_count).unwrap_or(1),
-                            )
-                            .smart_aim(false),
+                        let preview_time_slider = ui.add(
+                            Slider::new(&mut self.osd_preview.preview_time_secs, 0.0..=video_duration_secs)
+                                .suffix(" s")
+                                .smart_aim(false),
                         );
-                        if preview_frame_slider.changed() {
+                        if preview_time_slider.changed() {
                             self.update_osd_preview(ctx);
                         }
                     });
@@ -264,22 +923,23 @@ impl WalksnailOsdTool {
         let preview_width = image_rect.width();
         let preview_height = image_rect.height();
 
-        let grid_width = preview_width * 0.99375;
-        let grid_height = preview_height;
-        let cell_width = grid_width / 53.0;
-        let cell_height = grid_height / 20.0;
+        let character_size = get_character_size(video_width as u32, video_height as u32, &self.osd_options);
+        let cell_width = character_size.width() as f32 / video_width * preview_width;
+        let cell_height = character_size.height() as f32 / video_height * preview_height;
 
         let painter = ui.painter_at(image_rect);
 
-        let horizontal_offset = self.osd_options.position.x as f32 / video_width * preview_width;
-        let vertical_offset = self.osd_options.position.y as f32 / video_height * preview_height;
+        let grid_x = grid_offset(video_width as u32, GRID_WIDTH, character_size.width());
+        let grid_y = grid_offset(video_height as u32, GRID_HEIGHT, character_size.height());
+        let horizontal_offset = (grid_x + self.osd_options.position.x) as f32 / video_width * preview_width;
+        let vertical_offset = (grid_y + self.osd_options.position.y) as f32 / video_height * preview_height;
 
         let response = ui
             .allocate_rect(image_rect, Sense::click())
             .on_hover_cursor(CursorIcon::Crosshair);
 
-        for i in 0..53 {
-            for j in 0..20 {
+        for i in 0..GRID_WIDTH as u32 {
+            for j in 0..GRID_HEIGHT as u32 {
                 let rect = Rect::from_min_size(
                     top_left
                         + vec2(i as f32 * cell_width, j as f32 * cell_height)
@@ -313,13 +973,13 @@ impl WalksnailOsdTool {
 
         let line_stroke = Stroke::new(1.0, Color32::GRAY.gamma_multiply(0.5));
 
-        for i in 0..=53 {
+        for i in 0..=GRID_WIDTH {
             let x = top_left.x + i as f32 * cell_width + horizontal_offset;
             let y_min = image_rect.y_range().min + vertical_offset;
             let y_max = image_rect.y_range().max + vertical_offset;
             painter.vline(x, y_min..=y_max, line_stroke);
         }
-        for i in 0..=20 {
+        for i in 0..=GRID_HEIGHT {
             let x_min = image_rect.x_range().min + horizontal_offset;
             let x_max = image_rect.x_range().max + horizontal_offset;
             let y = top_left.y + i as f32 * cell_height + vertical_offset;
@@ -327,8 +987,10 @@ impl WalksnailOsdTool {
         }
     }
 
-    fn rendering_options(&mut self, ui: &mut Ui) {
+    fn rendering_options(&mut self, ui: &mut Ui, ctx: &egui::Context) {
         let mut changed = false;
+        let mut layers_changed = false;
+        let mut preview_changed = false;
         CollapsingHeader::new(RichText::new("Rendering Options").heading())
             .default_open(true)
             .show_unindented(ui, |ui| {
@@ -377,20 +1039,101 @@ impl WalksnailOsdTool {
                         changed |= ui.add(Slider::new(&mut self.render_settings.bitrate_mbps, 0..=160).text("Mbps")).changed();
                         ui.end_row();
 
+                        ui.label("Blending").on_hover_text(tooltip_text("How the overlay is blended with the video. sRGB blends like most image editors. Linear light keeps the brightness of the anti-aliased edges of the glyphs, which looks closer to the goggles. The ffmpeg overlay filter and YUV render modes always blend in sRGB."));
+                        egui::ComboBox::from_id_source("blend_mode")
+                            .selected_text(self.render_settings.blend_mode.to_string())
+                            .show_ui(ui, |ui| {
+                                for blend_mode in BlendMode::ALL {
+                                    let text = blend_mode.to_string();
+                                    preview_changed |= ui.selectable_value(&mut self.render_settings.blend_mode, blend_mode, text).changed();
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Render mode").on_hover_text(tooltip_text("Draw on decoded video: every video frame is decoded, the overlay is drawn on it and the frame is encoded again.\nffmpeg overlay filter: only the overlay is rendered and ffmpeg combines it with the video. This is faster for high resolution videos.\nDraw on decoded YUV video: like drawing on decoded video, but the frames are not converted to RGBA and back. The transparent export always uses RGBA frames."));
+                        egui::ComboBox::from_id_source("render_mode")
+                            .selected_text(self.render_settings.render_mode.to_string())
+                            .show_ui(ui, |ui| {
+                                for mode in RenderMode::ALL {
+                                    let text = mode.to_string();
+                                    changed |= ui.selectable_value(&mut self.render_settings.render_mode, mode, text).changed();
+                                }
+                            });
+                        ui.end_row();
+
                         ui.label("Upscale to 1440p").on_hover_text(tooltip_text("Upscale the output video to 1440p to get better quality after uplaoding to YouTube."));
                         changed |= ui.add(Checkbox::without_text(&mut self.render_settings.upscale)).changed();
                         ui.end_row();
 
-                        ui.label("Chroma key").on_hover_text(tooltip_text("Render the video with a chroma key instead of the input video so the OSD can be overlay in video editing software."));
+                        ui.label("Glyph scaling").on_hover_text(tooltip_text("Filter used to scale the font characters to the OSD size. Nearest neighbor keeps pixel art fonts sharp, Lanczos gives the smoothest result for high resolution fonts. Sizes that are an exact multiple of the font size always use integer scaling."));
+                        egui::ComboBox::from_id_source("glyph_scaling_filter")
+                            .selected_text(self.render_settings.glyph_scaling_filter.to_string())
+                            .show_ui(ui, |ui| {
+                                for filter in ScalingFilter::ALL {
+                                    let text = filter.to_string();
+                                    preview_changed |= ui.selectable_value(&mut self.render_settings.glyph_scaling_filter, filter, text).changed();
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Layers").on_hover_text(tooltip_text("Select which of the loaded files are rendered on top of the video and the order they are drawn in. Layers later in the list are drawn on top. Layers without a loaded file are skipped."));
+                        ui.horizontal(|ui| {
+                            let mut move_earlier = None;
+                            for (index, kind) in self.render_settings.layer_order.iter().enumerate() {
+                                if index > 0 && ui.small_button("⬅").on_hover_text("Draw earlier").clicked() {
+                                    move_earlier = Some(index);
+                                }
+                                let enabled = match kind {
+                                    LayerKind::Osd => &mut self.render_settings.render_osd,
+                                    LayerKind::Srt => &mut self.render_settings.render_srt,
+                                    LayerKind::Images => &mut self.render_settings.render_images,
+                                    LayerKind::Texts => &mut self.render_settings.render_texts,
+                                };
+                                layers_changed |= ui.checkbox(enabled, kind.to_string()).changed();
+                            }
+                            if let Some(index) = move_earlier {
+                                self.render_settings.layer_order.swap(index - 1, index);
+                                layers_changed = true;
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label("Transparent export").on_hover_text(tooltip_text("Render only the OSD and SRT data to a file with an alpha channel so it can be overlaid in video editing software without chroma key fringes."));
                         ui.horizontal(|ui| {
-                            changed |= ui.add(Checkbox::without_text(&mut self.render_settings.use_chroma_key)).changed();
-                            changed |= ui.color_edit_button_rgb(&mut self.render_settings.chroma_key).changed();
+                            changed |= ui.add(Checkbox::without_text(&mut self.render_settings.use_transparent_export)).changed();
+                            ui.add_enabled_ui(self.render_settings.use_transparent_export, |ui| {
+                                egui::ComboBox::from_id_source("transparent_format")
+                                    .selected_text(self.render_settings.transparent_format.to_string())
+                                    .show_ui(ui, |ui| {
+                                        for format in TransparentFormat::ALL {
+                                            let text = format.to_string();
+                                            changed |= ui.selectable_value(&mut self.render_settings.transparent_format, format, text).changed();
+                                        }
+                                    });
+                            });
+                        });
+                        ui.end_row();
+
+                        ui.label("Chroma key").on_hover_text(tooltip_text("Render the video with a chroma key instead of the input video so the OSD can be overlay in video editing software."));
+                        ui.add_enabled_ui(!self.render_settings.use_transparent_export, |ui| {
+                            ui.horizontal(|ui| {
+                                changed |= ui.add(Checkbox::without_text(&mut self.render_settings.use_chroma_key)).changed();
+                                changed |= ui.color_edit_button_rgb(&mut self.render_settings.chroma_key).changed();
+                            });
                         });
                         ui.end_row();
                     });
             });
 
-        if changed {
+        if preview_changed {
+            self.font_atlas = None;
+        }
+
+        if layers_changed || preview_changed {
+            self.update_osd_preview(ctx);
+        }
+
+        if changed || layers_changed || preview_changed {
             self.config_changed = Some(Instant::now());
         }
     }
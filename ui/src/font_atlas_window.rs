@@ -0,0 +1,151 @@
+use backend::font::{CharacterSize, FontType, FontValidationReport};
+use egui::{
+    vec2, Color32, ColorImage, Frame, Grid, Image, RichText, ScrollArea, TextureHandle, TextureOptions, Window,
+};
+
+use super::WalksnailOsdTool;
+
+const COLUMNS: usize = 16;
+const PREVIEW_SIZE: CharacterSize = CharacterSize::Small;
+
+/// Textures of the characters of the loaded font and the validation report, created when the window is opened.
+pub struct FontAtlas {
+    textures: Vec<TextureHandle>,
+    report: Option<FontValidationReport>,
+}
+
+impl WalksnailOsdTool {
+    pub fn render_font_atlas_window(&mut self, ctx: &egui::Context) {
+        if !self.font_atlas_window_open {
+            return;
+        }
+        if self.font_atlas.is_none() {
+            self.font_atlas = self.create_font_atlas(ctx);
+        }
+
+        let mut open = self.font_atlas_window_open;
+        let mut refresh = false;
+
+        Window::new("Font")
+            .open(&mut open)
+            .collapsible(false)
+            .default_size(vec2(700.0, 500.0))
+            .show(ctx, |ui| {
+                let (Some(font_file), Some(font_atlas)) = (&self.font_file, &self.font_atlas) else {
+                    ui.label("No font loaded");
+                    return;
+                };
+
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "{}, {} characters of {}",
+                        font_file.file_path.file_name().unwrap_or_default().to_string_lossy(),
+                        font_file.character_count * font_file.font_type.pages(),
+                        font_file.character_size
+                    ));
+                    refresh |= ui.button("Refresh").clicked();
+                });
+
+                match &font_atlas.report {
+                    None => {
+                        ui.label("Load an OSD file to check the font for problems");
+                    }
+                    Some(report) if report.is_ok() => {
+                        ui.label("No problems found for the glyphs used by the OSD file");
+                    }
+                    Some(report) => {
+                        let mut problem = |text: String| ui.label(RichText::new(text).color(Color32::RED));
+                        if report.missing_alpha {
+                            problem("The font image has no alpha channel".into());
+                        }
+                        if !report.transparent_glyphs.is_empty() {
+                            problem(format!("Transparent glyphs: {:?}", report.transparent_glyphs));
+                        }
+                        if !report.empty_glyphs.is_empty() {
+                            problem(format!("Empty glyphs: {:?}", report.empty_glyphs));
+                        }
+                        if !report.out_of_range_glyphs.is_empty() {
+                            problem(format!(
+                                "Glyphs missing from the font: {:?}",
+                                report.out_of_range_glyphs
+                            ));
+                        }
+                    }
+                }
+
+                ui.separator();
+
+                let characters_per_page = font_file.character_count as usize;
+                let page_label = if font_file.font_type == FontType::FourColor {
+                    "Color"
+                } else {
+                    "Page"
+                };
+                let color_page = self.osd_options.color_page;
+                let used_with_problem = |index: usize| {
+                    let Some((glyph_index, page)) = font_file.glyph_index(index) else {
+                        return false;
+                    };
+                    // Only the selected column of a four color font is used by the OSD
+                    let used_page = font_file.font_type != FontType::FourColor || page == color_page;
+                    used_page
+                        && font_atlas.report.as_ref().is_some_and(|report| {
+                            report.transparent_glyphs.contains(&glyph_index)
+                                || report.empty_glyphs.contains(&glyph_index)
+                        })
+                };
+
+                ScrollArea::vertical().show(ui, |ui| {
+                    Grid::new("font_atlas").spacing(vec2(4.0, 4.0)).show(ui, |ui| {
+                        for (index, texture) in font_atlas.textures.iter().enumerate() {
+                            let page = index / characters_per_page;
+                            let character = index % characters_per_page;
+                            ui.vertical(|ui| {
+                                Frame::none().fill(Color32::from_gray(90)).show(ui, |ui| {
+                                    ui.add(Image::new(texture).fit_to_exact_size(texture.size_vec2()));
+                                });
+                                let text = RichText::new(character.to_string()).small();
+                                let text = if used_with_problem(index) {
+                                    text.color(Color32::RED)
+                                } else {
+                                    text
+                                };
+                                ui.label(text)
+                                    .on_hover_text(format!("Index {index}, {page_label} {}", page + 1));
+                            });
+                            if (index + 1) % COLUMNS == 0 {
+                                ui.end_row();
+                            }
+                        }
+                    });
+                });
+            });
+
+        self.font_atlas_window_open = open;
+        if refresh {
+            self.font_atlas = None;
+        }
+    }
+
+    fn create_font_atlas(&self, ctx: &egui::Context) -> Option<FontAtlas> {
+        let font_file = self.font_file.as_ref()?;
+        let textures = font_file
+            .characters(&PREVIEW_SIZE, self.render_settings.glyph_scaling_filter)
+            .iter()
+            .enumerate()
+            .map(|(index, character)| {
+                let image = ColorImage::from_rgba_unmultiplied(
+                    [character.width() as usize, character.height() as usize],
+                    character.as_raw(),
+                );
+                ctx.load_texture(format!("font_character_{index}"), image, TextureOptions::NEAREST)
+            })
+            .collect();
+        let report = self
+            .osd_file
+            .as_ref()
+            .map(|osd_file| FontValidationReport::new(font_file, &osd_file.frames, &self.osd_options));
+
+        Some(FontAtlas { textures, report })
+    }
+}
@@ -0,0 +1,95 @@
+use std::time::Instant;
+
+use backend::osd::FcFirmware;
+use egui::{vec2, Align2, Grid, RichText, ScrollArea, Window};
+
+use super::WalksnailOsdTool;
+
+impl WalksnailOsdTool {
+    pub fn render_font_library_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.font_library_window_open;
+        let mut changed = false;
+        let mut tag_changed = false;
+
+        Window::new("Font library")
+            .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+            .open(&mut open)
+            .collapsible(false)
+            .default_height(400.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let directory = self
+                        .font_library
+                        .directory
+                        .as_ref()
+                        .map(|d| d.to_string_lossy().to_string())
+                        .unwrap_or("No directory selected".into());
+                    ui.label(directory);
+
+                    if ui.button("Select").clicked() {
+                        if let Some(directory) = rfd::FileDialog::new().pick_folder() {
+                            self.font_library.directory = Some(directory);
+                            changed |= true;
+                        }
+                    }
+                    if ui.button("Rescan").clicked() {
+                        changed |= true;
+                    }
+                });
+
+                ui.add_space(5.0);
+
+                if self.font_library.entries().is_empty() {
+                    ui.label("No fonts found");
+                    return;
+                }
+
+                ScrollArea::vertical().show(ui, |ui| {
+                    Grid::new("font_library").striped(true).show(ui, |ui| {
+                        ui.label(RichText::new("File").strong());
+                        ui.label(RichText::new("Size").strong());
+                        ui.label(RichText::new("Firmware").strong());
+                        ui.end_row();
+
+                        for entry in self.font_library.entries().to_vec() {
+                            ui.label(&entry.file_name);
+                            ui.label(entry.character_size.to_string());
+
+                            let tag = self.font_library.firmware_tags.get(&entry.file_name).copied();
+                            let auto_detect_text = format!(
+                                "Auto ({})",
+                                entry.detected_firmware.map(|f| f.to_string()).unwrap_or("none".into())
+                            );
+                            let mut selected = tag;
+                            egui::ComboBox::from_id_source(&entry.file_name)
+                                .selected_text(tag.map(|f| f.to_string()).unwrap_or(auto_detect_text.clone()))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut selected, None, auto_detect_text);
+                                    for firmware in FcFirmware::KNOWN {
+                                        ui.selectable_value(&mut selected, Some(firmware), firmware.to_string());
+                                    }
+                                });
+                            if selected != tag {
+                                self.font_library.set_firmware_tag(&entry.file_name, selected);
+                                tag_changed |= true;
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+            });
+
+        self.font_library_window_open = open;
+
+        if changed {
+            self.font_library.scan().ok();
+            self.config_changed = Some(Instant::now());
+        }
+
+        if tag_changed {
+            // The tag can change which library font matches the OSD file
+            self.select_library_font();
+            self.config_changed = Some(Instant::now());
+        }
+    }
+}
@@ -15,9 +15,12 @@ use crate::util::check_updates;
 mod app;
 mod bottom_panel;
 mod central_panel;
+mod font_atlas_window;
+mod font_library_window;
 mod osd_preview;
 mod render_status;
 mod side_panel;
+mod static_layers;
 mod top_panel;
 mod util;
 
@@ -1,29 +1,21 @@
 use backend::{
     font,
-    osd::{self, OsdOptions},
-    overlay::{overlay_osd, overlay_srt_data},
-    srt::{self, SrtOptions},
+    osd::{self, GRID_HEIGHT, GRID_WIDTH},
+    overlay::{grid_offset, OverlayLayer},
 };
 use image::RgbaImage;
 
-#[tracing::instrument(skip(osd_frame, srt_frame, font), level = "debug")]
+#[tracing::instrument(skip(layers), level = "debug")]
 pub fn create_osd_preview(
     width: u32,
     height: u32,
-    osd_frame: &osd::Frame,
-    srt_frame: &srt::SrtFrame,
-    font: &font::FontFile,
-    srt_font: &rusttype::Font,
-    osd_options: &OsdOptions,
-    srt_options: &SrtOptions,
+    layers: &[Box<dyn OverlayLayer>],
+    video_time_secs: f32,
 ) -> RgbaImage {
     let mut image = RgbaImage::new(width, height);
-
-    overlay_osd(&mut image, osd_frame, font, osd_options);
-    if let Some(srt_data) = &srt_frame.data {
-        overlay_srt_data(&mut image, srt_data, srt_font, srt_options);
+    for layer in layers {
+        layer.draw(&mut image, video_time_secs);
     }
-
     image
 }
 
@@ -31,16 +23,17 @@ pub fn create_osd_preview(
 pub fn calculate_horizontal_offset(width: u32, osd_frame: &osd::Frame, character_size: &font::CharacterSize) -> i32 {
     let min_x_grid = osd_frame.glyphs.iter().map(|g| g.grid_position.x).min().unwrap();
     let max_x_grid = osd_frame.glyphs.iter().map(|g| g.grid_position.x).max().unwrap();
-    let pixel_range = (max_x_grid - min_x_grid + 1) * character_size.width();
-    let offset = (width - pixel_range) / 2 - min_x_grid * character_size.width();
-    offset as i32
+    let pixel_range = ((max_x_grid - min_x_grid + 1) * character_size.width()) as i32;
+    let offset = (width as i32 - pixel_range) / 2 - (min_x_grid * character_size.width()) as i32;
+    // The position is relative to the centered grid
+    offset - grid_offset(width, GRID_WIDTH, character_size.width())
 }
 
 #[tracing::instrument(level = "debug")]
 pub fn calculate_vertical_offset(height: u32, osd_frame: &osd::Frame, character_size: &font::CharacterSize) -> i32 {
     let min_y_grid = osd_frame.glyphs.iter().map(|g| g.grid_position.y).min().unwrap();
     let max_y_grid = osd_frame.glyphs.iter().map(|g| g.grid_position.y).max().unwrap();
-    let pixel_range = (max_y_grid - min_y_grid + 1) * character_size.height();
-    let offset = (height - pixel_range) / 2 - min_y_grid * character_size.height();
-    offset as i32
+    let pixel_range = ((max_y_grid - min_y_grid + 1) * character_size.height()) as i32;
+    let offset = (height as i32 - pixel_range) / 2 - (min_y_grid * character_size.height()) as i32;
+    offset - grid_offset(height, GRID_HEIGHT, character_size.height())
 }
@@ -1,4 +1,4 @@
-use backend::font::FontType;
+use backend::font::{FontFormat, FontType};
 use egui::{CollapsingHeader, RichText, Ui};
 use egui_extras::{Column, TableBuilder};
 
@@ -292,7 +292,10 @@ impl WalksnailOsdTool {
                                 });
                                 row.col(|ui| {
                                     if let Some(font_file) = font_file {
-                                        ui.label(font_file.character_size.to_string());
+                                        match font_file.format {
+                                            FontFormat::Png => ui.label(font_file.character_size.to_string()),
+                                            FontFormat::Mcm => ui.label(font_file.format.to_string()),
+                                        };
                                     } else {
                                         ui.label("-");
                                     }
@@ -0,0 +1,127 @@
+use std::sync::Arc;
+
+use backend::overlay::{BlendMode, ImageLayer, ImageLayerOptions, OverlayLayerError, TextLayer, TextLayerOptions};
+
+use super::WalksnailOsdTool;
+
+/// Image and text layers opened from the render settings. They are kept between preview updates so the image and font
+/// files are only decoded again when their path changes, and unchanged layers keep their rendered image.
+#[derive(Default)]
+pub struct StaticLayers {
+    images: Vec<OpenedLayer<ImageLayerOptions, ImageLayer>>,
+    texts: Vec<OpenedLayer<TextLayerOptions, TextLayer>>,
+}
+
+struct OpenedLayer<O, L> {
+    options: O,
+    blend_mode: BlendMode,
+    layer: Result<Arc<L>, String>,
+}
+
+impl StaticLayers {
+    pub fn images(&self) -> impl Iterator<Item = &Arc<ImageLayer>> {
+        self.images.iter().filter_map(|opened| opened.layer.as_ref().ok())
+    }
+
+    pub fn texts(&self) -> impl Iterator<Item = &Arc<TextLayer>> {
+        self.texts.iter().filter_map(|opened| opened.layer.as_ref().ok())
+    }
+
+    /// Error of the image layer at `index` in the render settings, if it can't be opened.
+    pub fn image_error(&self, index: usize) -> Option<&str> {
+        self.images
+            .get(index)
+            .and_then(|opened| opened.layer.as_ref().err())
+            .map(String::as_str)
+    }
+
+    /// Error of the text layer at `index` in the render settings, if it can't be opened.
+    pub fn text_error(&self, index: usize) -> Option<&str> {
+        self.texts
+            .get(index)
+            .and_then(|opened| opened.layer.as_ref().err())
+            .map(String::as_str)
+    }
+}
+
+/// Update `opened` to the layers for `options`. Layers with unchanged options are kept, the others are created from the
+/// previous layer at the same position so the file is only opened again if its path changed. Layers that failed to
+/// open are only opened again with other options or when `retry_failed` is set.
+fn update_layers<O: Clone + PartialEq, L>(
+    opened: &mut Vec<OpenedLayer<O, L>>,
+    options: &[O],
+    blend_mode: BlendMode,
+    retry_failed: bool,
+    open: impl Fn(&O, Option<&L>) -> Result<L, OverlayLayerError>,
+) {
+    let mut previous = std::mem::take(opened).into_iter();
+    for options in options {
+        let previous = previous.next();
+        let opened_layer = match previous {
+            Some(previous)
+                if previous.options == *options
+                    && previous.blend_mode == blend_mode
+                    && (previous.layer.is_ok() || !retry_failed) =>
+            {
+                previous
+            }
+            previous => {
+                let previous_layer = previous.as_ref().and_then(|p| p.layer.as_ref().ok());
+                let layer = open(options, previous_layer.map(Arc::as_ref))
+                    .map(Arc::new)
+                    .map_err(|e| {
+                        tracing::error!("Failed to open layer: {}", e);
+                        e.to_string()
+                    });
+                OpenedLayer {
+                    options: options.clone(),
+                    blend_mode,
+                    layer,
+                }
+            }
+        };
+        opened.push(opened_layer);
+    }
+}
+
+impl WalksnailOsdTool {
+    /// Open the image and text layers that changed in the render settings. With `retry_failed` the layers that failed
+    /// to open are opened again, for example after a missing file was restored.
+    pub fn update_static_layers(&mut self, retry_failed: bool) {
+        let blend_mode = self.render_settings.blend_mode;
+        update_layers(
+            &mut self.static_layers.images,
+            &self.render_settings.image_layers,
+            blend_mode,
+            retry_failed,
+            |options, previous| match previous {
+                Some(previous) => previous.with_options(options, blend_mode),
+                None => ImageLayer::open(options, blend_mode),
+            },
+        );
+        update_layers(
+            &mut self.static_layers.texts,
+            &self.render_settings.text_layers,
+            blend_mode,
+            retry_failed,
+            |options, previous| match previous {
+                Some(previous) => previous.with_options(options, blend_mode),
+                None => TextLayer::open(options, blend_mode),
+            },
+        );
+    }
+
+    /// Error of the first enabled image or text layer that can't be opened. The render isn't started while there is
+    /// one, instead of rendering the video without the layer.
+    pub fn static_layers_error(&self) -> Option<String> {
+        let layers = &self.static_layers;
+        let mut errors = Vec::new();
+        if self.render_settings.render_images {
+            errors.extend(layers.images.iter().filter_map(|opened| opened.layer.as_ref().err()));
+        }
+        if self.render_settings.render_texts {
+            errors.extend(layers.texts.iter().filter_map(|opened| opened.layer.as_ref().err()));
+        }
+        errors.first().map(|error| error.to_string())
+    }
+}
@@ -23,7 +23,7 @@ impl WalksnailOsdTool {
             .clicked()
         {
             if let Some(file_handles) = rfd::FileDialog::new()
-                .add_filter("Avatar files", &["mp4", "osd", "png", "srt"])
+                .add_filter("Avatar files", &["mp4", "osd", "png", "mcm", "srt", "ttf", "otf"])
                 .pick_files()
             {
                 tracing::info!("Opened files {:?}", file_handles);
@@ -31,6 +31,7 @@ impl WalksnailOsdTool {
                 self.import_osd_file(&file_handles);
                 self.import_font_file(&file_handles);
                 self.import_srt_file(&file_handles);
+                self.import_srt_font_file(&file_handles);
 
                 self.update_osd_preview(ctx);
                 self.render_status.reset();
@@ -51,6 +52,7 @@ impl WalksnailOsdTool {
             self.import_osd_file(&file_handles);
             self.import_font_file(&file_handles);
             self.import_srt_file(&file_handles);
+            self.import_srt_font_file(&file_handles);
             self.update_osd_preview(ctx);
             self.render_status.reset();
         }
@@ -65,9 +67,10 @@ impl WalksnailOsdTool {
             self.video_info = None;
             self.osd_file = None;
             self.font_file = None;
+            self.font_atlas = None;
             self.srt_file = None;
             self.osd_preview.texture_handle = None;
-            self.osd_preview.preview_frame = 1;
+            self.osd_preview.preview_time_secs = 0.0;
             self.render_status.reset();
             tracing::info!("Reset files");
         }
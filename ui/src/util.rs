@@ -4,7 +4,14 @@ use std::{
     time::{Duration, Instant},
 };
 
-use backend::{config::AppConfig, ffmpeg::VideoInfo, font::FontFile, osd::OsdFile, srt::SrtFile};
+use backend::{
+    config::AppConfig,
+    ffmpeg::{TransparentFormat, VideoInfo},
+    font::{CharacterSize, FontFile},
+    osd::{self, OsdFile},
+    overlay::{get_character_size, osd_frame_index, LayerKind, OsdLayer, OverlayLayer, SrtLayer},
+    srt::{SrtFile, SrtFont, SrtFrame},
+};
 use egui::{FontFamily, FontId, Margin, RichText, Separator, TextStyle, Ui};
 use github_release_check::{GitHubReleaseItem, LookupError};
 use semver::Version;
@@ -15,8 +22,125 @@ use super::WalksnailOsdTool;
 use crate::util::build_info::Build;
 
 impl WalksnailOsdTool {
-    pub fn all_files_loaded(&self) -> bool {
-        self.video_loaded() && self.osd_loaded() && self.srt_loaded() && self.font_loaded()
+    pub fn osd_preview_loaded(&self) -> bool {
+        self.video_loaded() && self.osd_layer_file().is_some()
+    }
+
+    /// OSD file that should be rendered, if the OSD layer is enabled and can be drawn.
+    pub fn osd_layer_file(&self) -> Option<&OsdFile> {
+        if self.render_settings.render_osd && self.font_loaded() {
+            self.osd_file.as_ref()
+        } else {
+            None
+        }
+    }
+
+    /// SRT file that should be rendered, if the SRT layer is enabled.
+    pub fn srt_layer_file(&self) -> Option<&SrtFile> {
+        if self.render_settings.render_srt {
+            self.srt_file.as_ref()
+        } else {
+            None
+        }
+    }
+
+    fn layer_loaded(&self, kind: LayerKind) -> bool {
+        match kind {
+            LayerKind::Osd => self.osd_layer_file().is_some(),
+            LayerKind::Srt => self.srt_layer_file().is_some(),
+            LayerKind::Images => self.render_settings.render_images && !self.render_settings.image_layers.is_empty(),
+            LayerKind::Texts => self.render_settings.render_texts && !self.render_settings.text_layers.is_empty(),
+        }
+    }
+
+    /// Overlay layers in the configured order. The frame selectors pick the frames of the loaded files the layers
+    /// draw, the preview only needs a single frame while a render needs all of them. Image and text layers are taken
+    /// from [`WalksnailOsdTool::update_static_layers`], the ones that can't be opened are skipped.
+    pub fn overlay_layers(
+        &self,
+        osd_frames: impl Fn(&OsdFile) -> Vec<osd::Frame>,
+        srt_frames: impl Fn(&SrtFile) -> Vec<SrtFrame>,
+    ) -> Vec<Box<dyn OverlayLayer>> {
+        let video_size = self
+            .video_info
+            .as_ref()
+            .map_or((0, 0), |info| (info.width, info.height));
+        let mut layers: Vec<Box<dyn OverlayLayer>> = Vec::new();
+        for kind in &self.render_settings.layer_order {
+            match kind {
+                LayerKind::Osd => {
+                    if let (Some(osd_file), Some(font_file)) = (self.osd_layer_file(), &self.font_file) {
+                        layers.push(Box::new(OsdLayer::new(
+                            osd_frames(osd_file),
+                            font_file.clone(),
+                            &self.osd_options,
+                            self.render_settings.glyph_scaling_filter,
+                            video_size,
+                            self.render_settings.blend_mode,
+                        )));
+                    }
+                }
+                LayerKind::Srt => {
+                    if let Some(srt_file) = self.srt_layer_file() {
+                        layers.push(Box::new(SrtLayer::new(
+                            srt_frames(srt_file),
+                            self.srt_font.clone(),
+                            &self.srt_options,
+                            self.render_settings.blend_mode,
+                        )));
+                    }
+                }
+                LayerKind::Images if self.render_settings.render_images => {
+                    for layer in self.static_layers.images() {
+                        layers.push(Box::new(layer.clone()));
+                    }
+                }
+                LayerKind::Texts if self.render_settings.render_texts => {
+                    for layer in self.static_layers.texts() {
+                        layers.push(Box::new(layer.clone()));
+                    }
+                }
+                LayerKind::Images | LayerKind::Texts => {}
+            }
+        }
+        layers
+    }
+
+    /// Factor that stretches the OSD to the length of the video, when enabled.
+    pub fn osd_playback_speed_factor(&self) -> f32 {
+        match (&self.osd_file, &self.video_info) {
+            (Some(osd_file), Some(video_info)) if self.osd_options.adjust_playback_speed => {
+                video_info.duration.as_secs_f32() / osd_file.duration.as_secs_f32()
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// OSD frame that is shown at the preview time.
+    pub fn preview_osd_frame(&self) -> Option<&osd::Frame> {
+        let osd_file = self.osd_file.as_ref()?;
+        let index = osd_frame_index(
+            &osd_file.frames,
+            self.osd_playback_speed_factor(),
+            self.osd_preview.preview_time_secs,
+        )?;
+        osd_file.frames.get(index)
+    }
+
+    pub fn render_layers_description(&self) -> String {
+        let layers = self
+            .render_settings
+            .layer_order
+            .iter()
+            .filter(|kind| self.layer_loaded(**kind))
+            .map(|kind| kind.to_string())
+            .collect::<Vec<_>>();
+
+        if layers.is_empty() {
+            "Render the video without overlay".to_string()
+        } else {
+            format!("Render the video with {}", layers.join(" and "))
+        }
     }
 
     pub fn video_loaded(&self) -> bool {
@@ -49,7 +173,35 @@ impl WalksnailOsdTool {
     pub fn import_osd_file(&mut self, file_handles: &[PathBuf]) {
         if let Some(osd_file_path) = filter_file_with_extention(file_handles, "osd") {
             self.osd_file = OsdFile::open(osd_file_path.clone()).ok();
-            self.osd_preview.preview_frame = 1;
+            self.osd_preview.preview_time_secs = 0.0;
+            self.font_atlas = None;
+            self.select_library_font();
+        }
+    }
+
+    /// Load the font from the library that matches the firmware of the OSD file and the resolution of the video. The
+    /// bundled font is used if there is no matching font in the library and no font file could be loaded.
+    pub fn select_library_font(&mut self) {
+        let Some(osd_file) = &self.osd_file else {
+            return;
+        };
+        let character_size = self
+            .video_info
+            .as_ref()
+            .map(|v| get_character_size(v.width, v.height, &self.osd_options))
+            .unwrap_or(CharacterSize::Large);
+
+        if let Some(entry) = self.font_library.select(osd_file.fc_firmware, &character_size) {
+            if self.font_file.as_ref().map(|f| &f.file_path) != Some(&entry.file_path) {
+                tracing::info!("Selected font {:?} from library", entry.file_path);
+                if let Ok(font_file) = FontFile::open(entry.file_path.clone()) {
+                    self.set_font_file(font_file);
+                    self.config_changed = Some(Instant::now());
+                }
+            }
+        } else if self.font_file.is_none() {
+            tracing::info!("No font file loaded, using the bundled font");
+            self.set_font_file(FontFile::bundled_text());
         }
     }
 
@@ -62,11 +214,52 @@ impl WalksnailOsdTool {
     }
 
     pub fn import_font_file(&mut self, file_handles: &[PathBuf]) {
-        if let Some(font_file_path) = filter_file_with_extention(file_handles, "png") {
-            self.font_file = FontFile::open(font_file_path.clone()).ok();
+        if let Some(font_file_path) =
+            filter_file_with_extention(file_handles, "png").or(filter_file_with_extention(file_handles, "mcm"))
+        {
+            if let Ok(font_file) = FontFile::open(font_file_path.clone()) {
+                self.set_font_file(font_file);
+            } else {
+                self.font_file = None;
+                self.font_atlas = None;
+            }
             self.config_changed = Some(Instant::now());
         }
     }
+
+    pub fn set_font_file(&mut self, font_file: FontFile) {
+        self.font_file = Some(font_file);
+        self.apply_glyph_overrides();
+    }
+
+    /// Apply the glyph overrides from the OSD options to the loaded font.
+    pub fn apply_glyph_overrides(&mut self) {
+        self.font_atlas = None;
+        if let Some(font_file) = &mut self.font_file {
+            self.glyph_override_error = font_file
+                .apply_overrides(&self.osd_options.glyph_overrides)
+                .err()
+                .map(|e| e.to_string());
+        }
+    }
+
+    pub fn import_srt_font_file(&mut self, file_handles: &[PathBuf]) {
+        if let Some(srt_font_path) =
+            filter_file_with_extention(file_handles, "ttf").or(filter_file_with_extention(file_handles, "otf"))
+        {
+            match SrtFont::open(srt_font_path.clone()) {
+                Ok(srt_font) => {
+                    self.srt_font = srt_font;
+                    self.srt_font_error = None;
+                    self.config_changed = Some(Instant::now());
+                }
+                Err(e) => {
+                    tracing::error!("Failed to open SRT font: {}", e);
+                    self.srt_font_error = Some(e.to_string());
+                }
+            }
+        }
+    }
 }
 
 pub fn filter_file_with_extention<'a>(files: &'a [PathBuf], extention: &'a str) -> Option<&'a PathBuf> {
@@ -101,11 +294,18 @@ pub fn format_minutes_seconds(duration: &Duration) -> String {
     format!("{}:{:0>2}", minutes, seconds)
 }
 
-pub fn get_output_video_path(input_video_path: &Path) -> PathBuf {
+pub fn get_output_video_path(input_video_path: &Path, transparent_format: Option<TransparentFormat>) -> PathBuf {
     let input_video_file_name = input_video_path.file_stem().unwrap().to_string_lossy();
-    let output_video_file_name = format!("{}_with_osd.mp4", input_video_file_name);
     let mut output_video_path = input_video_path.parent().unwrap().to_path_buf();
-    output_video_path.push(output_video_file_name);
+    match transparent_format {
+        // PNG frames are written to a directory with the name the video would have
+        Some(TransparentFormat::PngSequence) => {
+            output_video_path.push(format!("{}_with_osd", input_video_file_name));
+            output_video_path.push("%06d.png");
+        }
+        Some(format) => output_video_path.push(format!("{}_osd.{}", input_video_file_name, format.extension())),
+        None => output_video_path.push(format!("{}_with_osd.mp4", input_video_file_name)),
+    }
     output_video_path
 }
 
@@ -167,10 +367,19 @@ impl Into<AppConfig> for &mut WalksnailOsdTool {
             font_path: self
                 .font_file
                 .as_ref()
+                .filter(|f| !f.bundled)
                 .map(|f| f.file_path.clone())
                 .unwrap_or_default()
                 .to_string_lossy()
                 .to_string(),
+            font_library: self.font_library.clone(),
+            srt_font_path: self
+                .srt_font
+                .file_path
+                .clone()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
         }
     }
 }
@@ -375,4 +584,21 @@ mod tests {
         let current_version = version("0.1.0");
         assert!(!should_update_to_version(&current_version, &current_version));
     }
+
+    #[test]
+    fn output_path_for_transparent_formats() {
+        let input = Path::new("videos").join("Avatar0001.mp4");
+        assert_eq!(
+            get_output_video_path(&input, None),
+            Path::new("videos").join("Avatar0001_with_osd.mp4")
+        );
+        assert_eq!(
+            get_output_video_path(&input, Some(TransparentFormat::ProRes4444)),
+            Path::new("videos").join("Avatar0001_osd.mov")
+        );
+        assert_eq!(
+            get_output_video_path(&input, Some(TransparentFormat::PngSequence)),
+            Path::new("videos").join("Avatar0001_with_osd").join("%06d.png")
+        );
+    }
 }